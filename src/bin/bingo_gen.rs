@@ -0,0 +1,60 @@
+// Generate a day 4 bingo input file from a seed.
+//
+// usage: bingo_gen [--seed <n>] [--boards <n>] [--size <rows>x<cols>]
+//                  [--range <min>-<max>] [--classic] [-o <file>]
+
+use adventofcode_2021::day04::generator::{generate, GeneratorConfig};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bingo_gen [--seed <n>] [--boards <n>] [--size <rows>x<cols>] \
+         [--range <min>-<max>] [--classic] [-o <file>]"
+    );
+    process::exit(2);
+}
+
+fn num<T: FromStr>(x: Option<&str>) -> T {
+    x.and_then(|x| x.parse().ok()).unwrap_or_else(|| usage())
+}
+
+// "5x5" --> (5, 5), "0-99" --> (0, 99)
+fn pair(x: Option<String>, sep: char) -> (usize, usize) {
+    let x = x.unwrap_or_else(|| usage());
+    let (a, b) = x.split_once(sep).unwrap_or_else(|| usage());
+    (num(Some(a)), num(Some(b)))
+}
+
+fn main() -> io::Result<()> {
+    let mut config = GeneratorConfig::default();
+    let mut output = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => config.seed = num(args.next().as_deref()),
+            "--boards" => config.boards = num(args.next().as_deref()),
+            "--size" => (config.rows, config.cols) = pair(args.next(), 'x'),
+            "--range" => (config.min, config.max) = pair(args.next(), '-'),
+            "--classic" => config.classic = true,
+            "-o" => output = Some(args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    let input = match generate(&config) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("can't generate: {:?}", e);
+            process::exit(1);
+        }
+    };
+    match output {
+        Some(path) => fs::write(path, input),
+        None => io::stdout().lock().write_all(input.as_bytes()),
+    }
+}
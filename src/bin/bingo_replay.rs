@@ -0,0 +1,84 @@
+// Replay a day 4 bingo game in the terminal, or dump its events as JSON Lines.
+//
+// usage: bingo_replay <input> [--delay <ms>] [--step] [--no-pause] [--json]
+//                     [--pattern <shapes>]
+//
+// --pattern takes the shapes that win, comma separated: rows, columns,
+// diagonals, corners, x, blackout, or a mask like "#.#/.#./#.#". The default
+// is rows,columns.
+
+use adventofcode_2021::day04::events::{events, replay, write_json_lines, Speed};
+use adventofcode_2021::day04::{parse, WinPattern};
+use std::env;
+use std::fs::read_to_string;
+use std::io;
+use std::process;
+use std::time::Duration;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bingo_replay <input> [--delay <ms>] [--step] [--no-pause] [--json] \
+         [--pattern <shapes>]"
+    );
+    process::exit(2);
+}
+
+fn main() -> io::Result<()> {
+    let mut filename = None;
+    let mut speed = Speed::Delay(Duration::from_millis(500));
+    let mut pause_on_win = true;
+    let mut json = false;
+    let mut pattern = WinPattern::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delay" => {
+                let ms = args
+                    .next()
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or_else(|| usage());
+                speed = Speed::Delay(Duration::from_millis(ms));
+            }
+            "--step" => speed = Speed::Step,
+            "--no-pause" => pause_on_win = false,
+            "--json" => json = true,
+            "--pattern" => {
+                let spec = args.next().unwrap_or_else(|| usage());
+                pattern = WinPattern::parse(&spec).unwrap_or_else(|e| {
+                    eprintln!("--pattern {}: {:?}", spec, e);
+                    process::exit(2);
+                });
+            }
+            _ if filename.is_none() && !arg.starts_with("--") => filename = Some(arg),
+            _ => usage(),
+        }
+    }
+
+    let filename = filename.unwrap_or_else(|| usage());
+    let (nums, boards) = match parse(&read_to_string(&filename)?) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}: {:?}", filename, e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = pattern.check(&boards) {
+        eprintln!("{}: {:?}", filename, e);
+        process::exit(1);
+    }
+    let events = events(&boards, &nums, &pattern);
+
+    if json {
+        write_json_lines(&events, io::stdout().lock())
+    } else {
+        replay(
+            &boards,
+            &events,
+            speed,
+            pause_on_win,
+            io::stdin().lock(),
+            io::stdout().lock(),
+        )
+    }
+}
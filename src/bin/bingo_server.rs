@@ -0,0 +1,66 @@
+// Host a local multiplayer bingo game. See day04::server for the protocol.
+//
+// usage: bingo_server [--port <n>] [--draws <input>] [--seed <n>] [--delay <ms>]
+//                     [--pattern <shapes>]
+//
+// Draws are 0-99 unless --draws gives a file in the puzzle format, whose first
+// line is used. With --seed they're shuffled for every game. --pattern is as
+// for bingo_replay, rows,columns by default.
+
+use adventofcode_2021::day04::server::{serve, ServerConfig};
+use adventofcode_2021::day04::{parse, WinPattern};
+use std::env;
+use std::fs::read_to_string;
+use std::io;
+use std::net::TcpListener;
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: bingo_server [--port <n>] [--draws <input>] [--seed <n>] [--delay <ms>] \
+         [--pattern <shapes>]"
+    );
+    process::exit(2);
+}
+
+fn num<T: FromStr>(x: Option<String>) -> T {
+    x.and_then(|x| x.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn main() -> io::Result<()> {
+    let mut config = ServerConfig::default();
+    let mut port: u16 = 7777;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = num(args.next()),
+            "--seed" => config.seed = Some(num(args.next())),
+            "--delay" => config.delay = Duration::from_millis(num(args.next())),
+            "--draws" => {
+                let filename = args.next().unwrap_or_else(|| usage());
+                config.draws = match parse(&read_to_string(&filename)?) {
+                    Ok((nums, _)) => nums,
+                    Err(e) => {
+                        eprintln!("{}: {:?}", filename, e);
+                        process::exit(1);
+                    }
+                };
+            }
+            "--pattern" => {
+                let spec = args.next().unwrap_or_else(|| usage());
+                config.pattern = WinPattern::parse(&spec).unwrap_or_else(|e| {
+                    eprintln!("--pattern {}: {:?}", spec, e);
+                    process::exit(2);
+                });
+            }
+            _ => usage(),
+        }
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("bingo on {}", listener.local_addr()?);
+    serve(listener, config)
+}
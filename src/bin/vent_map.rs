@@ -0,0 +1,70 @@
+// Draw a day 5 vent field: the puzzle's ASCII diagram, or a heat map image.
+//
+// usage: vent_map <input> [-o <file.pgm|file.ppm>]
+//
+// Without -o the diagram is printed, which is only readable for small fields.
+// Either way the picture is cropped to the vents, and refused if it's too big.
+
+use adventofcode_2021::day05::render::{to_ascii, write_pgm, write_ppm, RenderError};
+use adventofcode_2021::day05::{parse, plot_points};
+use std::env;
+use std::fs::{read_to_string, File};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("usage: vent_map <input> [-o <file.pgm|file.ppm>]");
+    process::exit(2);
+}
+
+fn main() -> io::Result<()> {
+    let mut filename = None;
+    let mut output = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => output = Some(args.next().unwrap_or_else(|| usage())),
+            _ if filename.is_none() && !arg.starts_with('-') => filename = Some(arg),
+            _ => usage(),
+        }
+    }
+
+    let filename = filename.unwrap_or_else(|| usage());
+    let segments = match parse(&read_to_string(&filename)?) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}: {:?}", filename, e);
+            process::exit(1);
+        }
+    };
+    let points = match plot_points(segments) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}: {:?}", filename, e);
+            process::exit(1);
+        }
+    };
+
+    let drawn = match output {
+        Some(path) if path.ends_with(".pgm") => {
+            write_pgm(&points, BufWriter::new(File::create(path)?))
+        }
+        Some(path) if path.ends_with(".ppm") => {
+            write_ppm(&points, BufWriter::new(File::create(path)?))
+        }
+        Some(_) => usage(),
+        None => to_ascii(&points).and_then(|x| Ok(io::stdout().lock().write_all(x.as_bytes())?)),
+    };
+    match drawn {
+        Ok(()) => Ok(()),
+        Err(RenderError::Io(e)) => Err(e),
+        Err(RenderError::TooBig { width, height, limit }) => {
+            eprintln!(
+                "{}: the vents cover {} x {}, too big to draw (at most {} cells)",
+                filename, width, height, limit
+            );
+            process::exit(1);
+        }
+    }
+}
@@ -24,7 +24,7 @@ fn parse(input: &str) -> Vec<usize> {
 }
 
 fn load_input(filename: &str) -> Vec<usize> {
-    let input = read_to_string(&filename).unwrap();
+    let input = read_to_string(filename).unwrap();
     parse(&input)
 }
 
@@ -44,7 +44,7 @@ mod tests {
     fn example_1() {
         let depths = parse(EXAMPLE);
         let increasing = filter_increasing(&depths);
-        assert_eq!(7, increasing.iter().count());
+        assert_eq!(7, increasing.len());
     }
 
     #[test]
@@ -52,14 +52,14 @@ mod tests {
         let depths = parse(EXAMPLE);
         let windows = sum_3(&depths);
         let increasing = filter_increasing(&windows);
-        assert_eq!(5, increasing.iter().count());
+        assert_eq!(5, increasing.len());
     }
 
     #[test]
     fn part_1() {
         let depths = load_input("input/input01.txt");
         let increasing = filter_increasing(&depths);
-        assert_eq!(1288, increasing.iter().count());
+        assert_eq!(1288, increasing.len());
     }
 
     #[test]
@@ -67,6 +67,6 @@ mod tests {
         let depths = load_input("input/input01.txt");
         let windows = sum_3(&depths);
         let increasing = filter_increasing(&windows);
-        assert_eq!(1311, increasing.iter().count());
+        assert_eq!(1311, increasing.len());
     }
 }
@@ -28,7 +28,7 @@ fn parse(input: &str) -> Vec<Command> {
 }
 
 fn load_input(filename: &str) -> Vec<Command> {
-    let input = read_to_string(&filename).unwrap();
+    let input = read_to_string(filename).unwrap();
     parse(&input)
 }
 
@@ -4,7 +4,10 @@
 #![allow(dead_code)]
 
 use indoc::indoc;
+use num_bigint::BigUint;
+use num_traits::Zero;
 use std::fs::read_to_string;
+use std::io::BufRead;
 
 const EXAMPLE: &str = indoc! {"
     00100
@@ -26,10 +29,72 @@ fn parse(input: &str) -> Vec<String> {
 }
 
 fn load_input(filename: &str) -> Vec<String> {
-    let input = read_to_string(&filename).unwrap();
+    let input = read_to_string(filename).unwrap();
     parse(&input)
 }
 
+#[derive(Debug, Clone, PartialEq)]
+enum ReportError {
+    Empty,
+    InconsistentWidth {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    TooWide {
+        width: usize,
+        max: usize,
+    },
+    InvalidDigit {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    // ran out of bit positions with more than one reading left, i.e. duplicates
+    Unresolved {
+        rating: &'static str,
+        survivors: usize,
+    },
+    Io(String),
+}
+
+// Width of the report, i.e. the number of bit columns. All lines must agree.
+fn report_width(nums: &[String]) -> Result<usize, ReportError> {
+    let width = nums.first().ok_or(ReportError::Empty)?.len();
+    for (i, x) in nums.iter().enumerate() {
+        if x.len() != width {
+            return Err(ReportError::InconsistentWidth {
+                line: i + 1,
+                expected: width,
+                found: x.len(),
+            });
+        }
+    }
+    Ok(width)
+}
+
+// Check the report and convert each line to a digit Vec, in any radix from 2
+// to 36
+fn report_digits(nums: &[String], radix: u32) -> Result<Vec<Vec<u32>>, ReportError> {
+    assert!((2..=36).contains(&radix), "radix {} out of range", radix);
+    report_width(nums)?;
+    nums.iter()
+        .enumerate()
+        .map(|(i, x)| {
+            x.chars()
+                .enumerate()
+                .map(|(j, c)| {
+                    c.to_digit(radix).ok_or(ReportError::InvalidDigit {
+                        line: i + 1,
+                        column: j + 1,
+                        found: c,
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
 // Add two vecs element by element.
 // see https://stackoverflow.com/questions/41207666/how-do-i-add-two-rust-arrays-element-wise
 fn add_vecs(a: Vec<u32>, b: Vec<u32>) -> Vec<u32> {
@@ -37,96 +102,717 @@ fn add_vecs(a: Vec<u32>, b: Vec<u32>) -> Vec<u32> {
     for ((zref, aval), bval) in z.iter_mut().zip(&a).zip(&b) {
         *zref = aval + bval;
     }
-    return z;
+    z
 }
 
-fn add_vecs2(a: &Vec<u32>, b: &Vec<u32>) -> Vec<u32> {
+fn add_vecs2(a: &[u32], b: &[u32]) -> Vec<u32> {
     let mut z = vec![0; a.len()];
     for ((zref, aval), bval) in z.iter_mut().zip(a).zip(b) {
         *zref = aval + bval;
     }
-    return z;
+    z
+}
+
+// bit Vec --> int, for up to 128 bits.
+// Wider reports need from_digits_big().
+fn from_bits(bs: &[u32]) -> u128 {
+    from_digits(bs, 2)
+}
+
+// digit Vec --> int, as long as it fits in a u128 (see check_digits)
+fn from_digits(ds: &[u32], radix: u32) -> u128 {
+    ds.iter().fold(0u128, |acc, &d| {
+        acc.checked_mul(radix as u128)
+            .and_then(|x| x.checked_add(d as u128))
+            .expect("digits won't fit in a u128")
+    })
+}
+
+// digit Vec --> BigUint, for any number of digits
+fn from_digits_big(ds: &[u32], radix: u32) -> BigUint {
+    ds.iter().fold(BigUint::zero(), |acc, &d| acc * radix + d)
+}
+
+// Flip each digit to its complement, radix - 1 - d
+fn invert_digits(xs: &[u32], radix: u32) -> Vec<u32> {
+    xs.iter().map(|&x| radix - 1 - x).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Commonness {
+    Most,
+    Least,
+}
+
+// Which digit wins when several are equally common. In binary, Highest means
+// ties go to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TieBreak {
+    Lowest,
+    Highest,
 }
 
-// String --> bit Vec
-fn to_bits(x: &String) -> Vec<u32> {
-    x.chars().map(|c| c.to_digit(10).unwrap()).collect()
+// Rule for picking one digit from a column's counts. The puzzle's rule is the
+// default: most common bit, a 1 needs at least half the votes (ties go to 1).
+// Each rating pair uses a criteria for the first value (gamma, oxygen) and its
+// opposite() for the second (epsilon, co2).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DigitCriteria {
+    keep: Commonness,
+    tie: TieBreak,
+    // share of the votes a 1 needs to count as the most common bit, as
+    // (numerator, denominator) so ties are exact. In other radixes each
+    // digit's count is weighted from `threshold` for 0 to `1 - threshold` for
+    // the highest digit, so 1/2 is a plain count.
+    threshold: (u32, u32),
 }
 
-// bit Vec --> int
-// there must be an easier way... I feel dumb.
-fn from_bits(bs: &Vec<u32>) -> u32 {
-    let mut result = 0;
-    for bit in 0..bs.len() {
-        result |= bs[bs.len() - bit - 1] << bit;
+impl Default for DigitCriteria {
+    fn default() -> Self {
+        DigitCriteria {
+            keep: Commonness::Most,
+            tie: TieBreak::Highest,
+            threshold: (1, 2),
+        }
     }
-    result
 }
 
-fn invert(xs: &Vec<u32>) -> Vec<u32> {
-    xs.iter().map(|&x| if x == 1 { 0 } else { 1 }).collect()
+impl DigitCriteria {
+    fn most_common() -> Self {
+        DigitCriteria::default()
+    }
+
+    fn least_common() -> Self {
+        DigitCriteria::default().opposite()
+    }
+
+    // Most common bit, but a 1 needs more than half the votes
+    fn strict_majority() -> Self {
+        DigitCriteria::default().ties_to(TieBreak::Lowest)
+    }
+
+    fn ties_to(self, tie: TieBreak) -> Self {
+        DigitCriteria { tie, ..self }
+    }
+
+    // A 1 needs at least numerator/denominator of the votes
+    fn with_threshold(self, numerator: u32, denominator: u32) -> Self {
+        assert!(
+            numerator <= denominator && denominator > 0,
+            "threshold {}/{} out of range",
+            numerator,
+            denominator
+        );
+        DigitCriteria {
+            threshold: (numerator, denominator),
+            ..self
+        }
+    }
+
+    // Least common <--> most common, with the tie-break flipped to match
+    fn opposite(self) -> Self {
+        let keep = match self.keep {
+            Commonness::Most => Commonness::Least,
+            Commonness::Least => Commonness::Most,
+        };
+        let tie = match self.tie {
+            TieBreak::Lowest => TieBreak::Highest,
+            TieBreak::Highest => TieBreak::Lowest,
+        };
+        DigitCriteria { keep, tie, ..self }
+    }
+
+    // Pick a digit from a column's counts, indexed by digit. Digits with a count
+    // of 0 are candidates too, so the least common digit may not appear at all.
+    fn pick(&self, counts: &[usize]) -> u32 {
+        self.pick_from(counts, counts.iter().copied().enumerate())
+    }
+
+    // Same as pick(), but only from the digits that appear
+    fn pick_present(&self, counts: &[usize]) -> u32 {
+        let present = counts.iter().copied().enumerate().filter(|&(_, c)| c > 0);
+        self.pick_from(counts, present)
+    }
+
+    fn pick_from<I>(&self, counts: &[usize], candidates: I) -> u32
+    where
+        I: Iterator<Item = (usize, usize)>,
+    {
+        // weights scaled by denominator * (radix - 1), which keeps them whole
+        let top = (counts.len() - 1) as i128;
+        let (p, q) = (self.threshold.0 as i128, self.threshold.1 as i128);
+        let weight = |d: usize| p * top + (q - 2 * p) * d as i128;
+
+        let mut best: Option<(usize, i128)> = None;
+        for (d, c) in candidates {
+            let score = c as i128 * weight(d);
+            let better = match best {
+                None => true,
+                Some((_, best_score)) if score == best_score => self.tie == TieBreak::Highest,
+                Some((_, best_score)) => match self.keep {
+                    Commonness::Most => score > best_score,
+                    Commonness::Least => score < best_score,
+                },
+            };
+            if better {
+                best = Some((d, score));
+            }
+        }
+        best.map(|(d, _)| d as u32).expect("no digits to pick from")
+    }
 }
 
-// Find the "most common" value (the mode) for each bit
-fn modes(xs: &Vec<Vec<u32>>) -> Vec<u32> {
-    xs.iter()
-        .cloned()
-        .reduce(add_vecs)
-        .unwrap()
+// Pick a digit for each column, by default the "most common" value (the mode)
+fn digit_modes(xs: &[Vec<u32>], radix: u32, criteria: DigitCriteria) -> Vec<u32> {
+    digit_counts(xs, radix)
         .iter()
-        .map(|c| if c * 2 >= xs.len() as u32 { 1 } else { 0 })
+        .map(|c| criteria.pick(c))
         .collect()
 }
 
-fn gamma_epsilon(nums: &Vec<String>) -> (u32, u32) {
-    let bits: Vec<Vec<u32>> = nums.iter().map(to_bits).collect();
-    let mode_bits: Vec<u32> = modes(&bits);
+// Count of each digit in each column, as counts[column][digit]
+fn digit_counts(xs: &[Vec<u32>], radix: u32) -> Vec<Vec<usize>> {
+    let mut counts = vec![vec![0; radix as usize]; xs[0].len()];
+    for x in xs {
+        for (column, &d) in x.iter().enumerate() {
+            counts[column][d as usize] += 1;
+        }
+    }
+    counts
+}
+
+// (gamma, epsilon) as digit Vecs, so the caller can pick the integer type
+fn gamma_epsilon_digits(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(Vec<u32>, Vec<u32>), ReportError> {
+    let digits = report_digits(nums, radix)?;
+    let gamma = digit_modes(&digits, radix, criteria);
+    let epsilon = digit_modes(&digits, radix, criteria.opposite());
+    Ok((gamma, epsilon))
+}
+
+fn gamma_epsilon(nums: &[String], criteria: DigitCriteria) -> Result<(u128, u128), ReportError> {
+    gamma_epsilon_radix(nums, 2, criteria)
+}
+
+// gamma_epsilon() for any radix
+fn gamma_epsilon_radix(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(u128, u128), ReportError> {
+    let (gamma, epsilon) = gamma_epsilon_digits(nums, radix, criteria)?;
+    check_digits(gamma.len(), radix)?;
+    Ok((from_digits(&gamma, radix), from_digits(&epsilon, radix)))
+}
+
+fn gamma_epsilon_big(
+    nums: &[String],
+    criteria: DigitCriteria,
+) -> Result<(BigUint, BigUint), ReportError> {
+    gamma_epsilon_radix_big(nums, 2, criteria)
+}
+
+fn gamma_epsilon_radix_big(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(BigUint, BigUint), ReportError> {
+    let (gamma, epsilon) = gamma_epsilon_digits(nums, radix, criteria)?;
+    Ok((
+        from_digits_big(&gamma, radix),
+        from_digits_big(&epsilon, radix),
+    ))
+}
+
+// gamma * epsilon, which overflows a u128 once the report is wider than 64 bits
+fn power_consumption(nums: &[String], criteria: DigitCriteria) -> Result<BigUint, ReportError> {
+    power_consumption_radix(nums, 2, criteria)
+}
+
+fn power_consumption_radix(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<BigUint, ReportError> {
+    let (gamma, epsilon) = gamma_epsilon_radix_big(nums, radix, criteria)?;
+    Ok(gamma * epsilon)
+}
+
+fn check_width(width: usize) -> Result<(), ReportError> {
+    check_digits(width, 2)
+}
+
+// Does a reading of this many digits always fit in a u128?
+fn check_digits(width: usize, radix: u32) -> Result<(), ReportError> {
+    let max = max_digits(radix);
+    if width > max {
+        return Err(ReportError::TooWide { width, max });
+    }
+    Ok(())
+}
+
+// Most digits of this radix that fit in a u128, i.e. the largest w with radix^w <= 2^128
+fn max_digits(radix: u32) -> usize {
+    let limit = BigUint::from(1u8) << 128u32;
+    let mut max = 0;
+    let mut x = BigUint::from(radix);
+    while x <= limit {
+        max += 1;
+        x *= radix;
+    }
+    max
+}
+
+// Running count of ones per column, so gamma/epsilon can be computed from a
+// stream without holding on to the readings.
+#[derive(Debug, Default)]
+struct ColumnCounter {
+    lines: usize,
+    ones: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    lines: usize,
+    gamma: u128,
+    epsilon: u128,
+}
+
+impl ColumnCounter {
+    fn new() -> Self {
+        ColumnCounter::default()
+    }
 
-    let gamma = from_bits(&mode_bits);
-    let epsilon = from_bits(&invert(&mode_bits));
+    fn push(&mut self, line: &str) -> Result<(), ReportError> {
+        let line_no = self.lines + 1;
+        if self.lines == 0 {
+            self.ones = vec![0; line.len()];
+        } else if line.len() != self.ones.len() {
+            return Err(ReportError::InconsistentWidth {
+                line: line_no,
+                expected: self.ones.len(),
+                found: line.len(),
+            });
+        }
 
-    return (gamma, epsilon);
+        // check the whole line before counting any of it
+        let mut bits = Vec::with_capacity(line.len());
+        for (j, c) in line.chars().enumerate() {
+            match c {
+                '0' => bits.push(0),
+                '1' => bits.push(1),
+                _ => {
+                    return Err(ReportError::InvalidDigit {
+                        line: line_no,
+                        column: j + 1,
+                        found: c,
+                    })
+                }
+            }
+        }
+        for (count, bit) in self.ones.iter_mut().zip(bits) {
+            *count += bit;
+        }
+        self.lines += 1;
+        Ok(())
+    }
+
+    // gamma/epsilon for the readings seen so far
+    fn snapshot(&self, criteria: DigitCriteria) -> Result<Snapshot, ReportError> {
+        if self.lines == 0 {
+            return Err(ReportError::Empty);
+        }
+        check_width(self.ones.len())?;
+        let pick = |criteria: DigitCriteria| -> Vec<u32> {
+            self.ones
+                .iter()
+                .map(|&c| criteria.pick(&[self.lines - c, c]))
+                .collect()
+        };
+        Ok(Snapshot {
+            lines: self.lines,
+            gamma: from_bits(&pick(criteria)),
+            epsilon: from_bits(&pick(criteria.opposite())),
+        })
+    }
 }
 
-fn filter_bitmask(xs: Vec<Vec<u32>>, mask: Vec<u32>, bit: usize) -> Vec<Vec<u32>> {
-    xs.into_iter().filter(|x| x[bit] == mask[bit]).collect()
+// gamma/epsilon in a single pass over any reader, one line at a time.
+// on_snapshot is called with the running result after every `every` lines.
+fn gamma_epsilon_stream<R, F>(
+    reader: R,
+    criteria: DigitCriteria,
+    every: usize,
+    mut on_snapshot: F,
+) -> Result<(u128, u128), ReportError>
+where
+    R: BufRead,
+    F: FnMut(Snapshot),
+{
+    let mut counter = ColumnCounter::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| ReportError::Io(e.to_string()))?;
+        counter.push(&line)?;
+        if every > 0 && counter.lines.is_multiple_of(every) {
+            on_snapshot(counter.snapshot(criteria)?);
+        }
+    }
+    let last = counter.snapshot(criteria)?;
+    Ok((last.gamma, last.epsilon))
 }
 
-fn oxygen_co2(nums: &Vec<String>) -> (u32, u32) {
-    // oxygen: reduce the list of numbers by calculating the most common bit,
-    // at each position, and keeping ony the values that share that bit.
-    // Must recalc the most common bit after each pass, not up front for the whole list.
-    let mut i = 0;
-    let mut o2_bits: Vec<Vec<u32>> = nums.iter().map(to_bits).collect();
-    while o2_bits.len() > 1 {
-        let most_common: Vec<u32> = modes(&o2_bits);
-        o2_bits = filter_bitmask(o2_bits, most_common, i);
-        i += 1;
+// Trie of the readings, one level per digit, where each node counts the
+// readings below it. Built once, so each rating is a single walk from the root
+// to a leaf instead of re-filtering the whole list at every position.
+#[derive(Debug)]
+struct DigitTrie {
+    radix: usize,
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug)]
+struct TrieNode {
+    count: usize,
+    children: Vec<Option<usize>>,
+}
+
+impl DigitTrie {
+    fn new(radix: u32) -> Self {
+        let radix = radix as usize;
+        DigitTrie {
+            radix,
+            nodes: vec![TrieNode {
+                count: 0,
+                children: vec![None; radix],
+            }],
+        }
+    }
+
+    fn from_digits(xs: &[Vec<u32>], radix: u32) -> Self {
+        let mut trie = DigitTrie::new(radix);
+        for x in xs {
+            trie.insert(x);
+        }
+        trie
     }
 
-    // co2: same as above, but for the least common bit
-    let mut j = 0;
-    let mut co2_bits: Vec<Vec<u32>> = nums.iter().map(to_bits).collect();
-    while co2_bits.len() > 1 {
-        let least_common: Vec<u32> = invert(&modes(&co2_bits));
-        co2_bits = filter_bitmask(co2_bits, least_common, j);
-        j += 1;
+    fn from_bits(xs: &[Vec<u32>]) -> Self {
+        DigitTrie::from_digits(xs, 2)
     }
 
-    let oxygen = from_bits(&o2_bits[0]);
-    let co2 = from_bits(&co2_bits[0]);
+    fn insert(&mut self, digits: &[u32]) {
+        let mut node = 0;
+        self.nodes[node].count += 1;
+        for &d in digits {
+            node = match self.nodes[node].children[d as usize] {
+                Some(child) => child,
+                None => {
+                    let children = vec![None; self.radix];
+                    self.nodes.push(TrieNode { count: 0, children });
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children[d as usize] = Some(child);
+                    child
+                }
+            };
+            self.nodes[node].count += 1;
+        }
+    }
+
+    // Number of readings in the trie
+    fn len(&self) -> usize {
+        self.nodes[0].count
+    }
+
+    // How many readings start with these digits
+    fn count_prefix(&self, prefix: &[u32]) -> usize {
+        let mut node = 0;
+        for &d in prefix {
+            match self.nodes[node].children[d as usize] {
+                Some(child) => node = child,
+                None => return 0,
+            }
+        }
+        self.nodes[node].count
+    }
+
+    // Readings left after each step of following these digits, until only one is left
+    fn survivors(&self, digits: &[u32]) -> Vec<usize> {
+        let mut counts = vec![];
+        for i in 1..=digits.len() {
+            let count = self.count_prefix(&digits[..i]);
+            counts.push(count);
+            if count <= 1 {
+                break;
+            }
+        }
+        counts
+    }
+
+    // Readings below each child of this node, 0 for missing children
+    fn child_counts(&self, node: usize) -> Vec<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .map(|c| c.map_or(0, |c| self.nodes[c].count))
+            .collect()
+    }
+
+    // Walk from the root to a leaf. Where several branches exist, choose(counts)
+    // picks the digit to follow; where only one exists, that's the only way down.
+    // Returns the leaf's digits and how many readings ended up there.
+    fn walk<F>(&self, choose: F) -> (Vec<u32>, usize)
+    where
+        F: Fn(&[usize]) -> u32,
+    {
+        let mut digits = vec![];
+        let mut node = 0;
+        loop {
+            let present: Vec<usize> = (0..self.radix)
+                .filter(|&d| self.nodes[node].children[d].is_some())
+                .collect();
+            let d = match present[..] {
+                [] => break,
+                [only] => only as u32,
+                _ => choose(&self.child_counts(node)),
+            };
+            digits.push(d);
+            node = self.nodes[node].children[d as usize].unwrap();
+        }
+        (digits, self.nodes[node].count)
+    }
+}
+
+// (oxygen, co2) as digit Vecs
+fn oxygen_co2_digits(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(Vec<u32>, Vec<u32>), ReportError> {
+    let digits = report_digits(nums, radix)?;
+    let trie = DigitTrie::from_digits(&digits, radix);
+
+    // oxygen: follow the digit picked by the criteria at each position.
+    // co2: follow the digit picked by the opposite criteria.
+    let co2_criteria = criteria.opposite();
+    let oxygen = trie.walk(|c| criteria.pick_present(c));
+    let co2 = trie.walk(|c| co2_criteria.pick_present(c));
+
+    let oxygen = resolved("oxygen", oxygen)?;
+    let co2 = resolved("co2", co2)?;
+
+    Ok((oxygen, co2))
+}
+
+// A rating is only valid if the walk narrowed it down to a single reading
+fn resolved(rating: &'static str, leaf: (Vec<u32>, usize)) -> Result<Vec<u32>, ReportError> {
+    match leaf {
+        (digits, 1) => Ok(digits),
+        (_, survivors) => Err(ReportError::Unresolved { rating, survivors }),
+    }
+}
+
+fn oxygen_co2(nums: &[String], criteria: DigitCriteria) -> Result<(u128, u128), ReportError> {
+    oxygen_co2_radix(nums, 2, criteria)
+}
+
+// oxygen_co2() for any radix
+fn oxygen_co2_radix(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(u128, u128), ReportError> {
+    let (oxygen, co2) = oxygen_co2_digits(nums, radix, criteria)?;
+    check_digits(oxygen.len(), radix)?;
+    Ok((from_digits(&oxygen, radix), from_digits(&co2, radix)))
+}
+
+fn oxygen_co2_big(
+    nums: &[String],
+    criteria: DigitCriteria,
+) -> Result<(BigUint, BigUint), ReportError> {
+    oxygen_co2_radix_big(nums, 2, criteria)
+}
 
-    return (oxygen, co2);
+fn oxygen_co2_radix_big(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<(BigUint, BigUint), ReportError> {
+    let (oxygen, co2) = oxygen_co2_digits(nums, radix, criteria)?;
+    Ok((
+        from_digits_big(&oxygen, radix),
+        from_digits_big(&co2, radix),
+    ))
+}
+
+// oxygen * co2
+fn life_support_rating(nums: &[String], criteria: DigitCriteria) -> Result<BigUint, ReportError> {
+    life_support_rating_radix(nums, 2, criteria)
+}
+
+fn life_support_rating_radix(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<BigUint, ReportError> {
+    let (oxygen, co2) = oxygen_co2_radix_big(nums, radix, criteria)?;
+    Ok(oxygen * co2)
+}
+
+#[derive(Debug, PartialEq)]
+struct ColumnStats {
+    // readings with each digit in this column
+    counts: Vec<usize>,
+}
+
+impl ColumnStats {
+    // Least common count over most common count: 1.0 is a dead heat, 0.0 means
+    // some digit never appears
+    fn balance(&self) -> f64 {
+        let lo = self.counts.iter().min().copied().unwrap_or(0);
+        let hi = self.counts.iter().max().copied().unwrap_or(0);
+        if hi == 0 {
+            return 0.0;
+        }
+        lo as f64 / hi as f64
+    }
+
+    // Shannon entropy of the column, 0.0 to 1.0 (in bits for binary, and
+    // in base-radix digits otherwise)
+    fn entropy(&self) -> f64 {
+        let total: usize = self.counts.iter().sum();
+        let base = (self.counts.len() as f64).log2();
+        self.counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / total as f64;
+                -p * p.log2() / base
+            })
+            .sum()
+    }
+}
+
+// Per-column counts, plus how many readings survived each oxygen/co2 step,
+// to see how close each decision was.
+#[derive(Debug, PartialEq)]
+struct ColumnReport {
+    radix: u32,
+    columns: Vec<ColumnStats>,
+    oxygen_survivors: Vec<usize>,
+    co2_survivors: Vec<usize>,
+}
+
+impl ColumnReport {
+    // Binary reports name their columns zeros and ones, others by digit
+    fn digit_names(&self) -> Vec<String> {
+        match self.radix {
+            2 => vec!["zeros".to_string(), "ones".to_string()],
+            radix => (0..radix)
+                .map(|d| std::char::from_digit(d, radix).unwrap().to_string())
+                .collect(),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut table = format!("{:>3}", if self.radix == 2 { "bit" } else { "pos" });
+        for name in self.digit_names() {
+            table += &format!(" {:>6}", name);
+        }
+        table += "  balance  entropy     o2    co2\n";
+
+        let survivor =
+            |xs: &[usize], i: usize| xs.get(i).map_or("-".to_string(), |x| x.to_string());
+        for (i, c) in self.columns.iter().enumerate() {
+            table += &format!("{:>3}", i);
+            for count in &c.counts {
+                table += &format!(" {:>6}", count);
+            }
+            table += &format!(
+                " {:>8.3} {:>8.3} {:>6} {:>6}\n",
+                c.balance(),
+                c.entropy(),
+                survivor(&self.oxygen_survivors, i),
+                survivor(&self.co2_survivors, i),
+            );
+        }
+        table
+    }
+
+    fn to_json(&self) -> String {
+        let list = |xs: &[usize]| {
+            xs.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| {
+                let counts = match self.radix {
+                    2 => format!(r#""zeros":{},"ones":{}"#, c.counts[0], c.counts[1]),
+                    _ => format!(r#""counts":[{}]"#, list(&c.counts)),
+                };
+                format!(
+                    r#"{{{},"balance":{:.6},"entropy":{:.6}}}"#,
+                    counts,
+                    c.balance(),
+                    c.entropy()
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"columns":[{}],"oxygen_survivors":[{}],"co2_survivors":[{}]}}"#,
+            columns.join(","),
+            list(&self.oxygen_survivors),
+            list(&self.co2_survivors)
+        )
+    }
+}
+
+fn column_report(nums: &[String], criteria: DigitCriteria) -> Result<ColumnReport, ReportError> {
+    column_report_radix(nums, 2, criteria)
+}
+
+fn column_report_radix(
+    nums: &[String],
+    radix: u32,
+    criteria: DigitCriteria,
+) -> Result<ColumnReport, ReportError> {
+    let digits = report_digits(nums, radix)?;
+    let columns = digit_counts(&digits, radix)
+        .into_iter()
+        .map(|counts| ColumnStats { counts })
+        .collect();
+
+    // walk without checking the ratings resolved, so the report still shows
+    // where duplicate readings got stuck
+    let trie = DigitTrie::from_digits(&digits, radix);
+    let co2_criteria = criteria.opposite();
+    let (oxygen, _) = trie.walk(|c| criteria.pick_present(c));
+    let (co2, _) = trie.walk(|c| co2_criteria.pick_present(c));
+
+    Ok(ColumnReport {
+        radix,
+        columns,
+        oxygen_survivors: trie.survivors(&oxygen),
+        co2_survivors: trie.survivors(&co2),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn example_1() {
         let nums = parse(EXAMPLE);
-        let (gamma, epsilon) = gamma_epsilon(&nums);
+        let (gamma, epsilon) = gamma_epsilon(&nums, DigitCriteria::default()).unwrap();
         assert_eq!((22, 9), (gamma, epsilon));
         assert_eq!(198, gamma * epsilon);
     }
@@ -134,7 +820,7 @@ mod tests {
     #[test]
     fn example_2() {
         let nums = parse(EXAMPLE);
-        let (oxygen, co2) = oxygen_co2(&nums);
+        let (oxygen, co2) = oxygen_co2(&nums, DigitCriteria::default()).unwrap();
         assert_eq!((23, 10), (oxygen, co2));
         assert_eq!(230, oxygen * co2);
     }
@@ -142,14 +828,382 @@ mod tests {
     #[test]
     fn part_1() {
         let nums = load_input("input/input03.txt");
-        let (gamma, epsilon) = gamma_epsilon(&nums);
+        let (gamma, epsilon) = gamma_epsilon(&nums, DigitCriteria::default()).unwrap();
         assert_eq!(1540244, gamma * epsilon);
     }
 
     #[test]
     fn part_2() {
         let nums = load_input("input/input03.txt");
-        let (oxygen, co2) = oxygen_co2(&nums);
+        let (oxygen, co2) = oxygen_co2(&nums, DigitCriteria::default()).unwrap();
         assert_eq!(4203981, oxygen * co2);
     }
+
+    #[test]
+    fn wide_report() {
+        // 100 columns: gamma/epsilon still fit in a u128, but not their product
+        let a = "1".repeat(60) + &"0".repeat(40);
+        let b = "1".repeat(60) + &"1".repeat(40);
+        let c = "0".repeat(60) + &"0".repeat(40);
+        let nums = vec![a, b, c];
+
+        let (gamma, epsilon) = gamma_epsilon(&nums, DigitCriteria::default()).unwrap();
+        assert_eq!(((1u128 << 60) - 1) << 40, gamma);
+        assert_eq!((1u128 << 40) - 1, epsilon);
+        assert_eq!(
+            BigUint::from(gamma) * BigUint::from(epsilon),
+            power_consumption(&nums, DigitCriteria::default()).unwrap()
+        );
+
+        let (oxygen, co2) = oxygen_co2(&nums, DigitCriteria::default()).unwrap();
+        assert_eq!((1u128 << 100) - 1, oxygen);
+        assert_eq!(0, co2);
+    }
+
+    #[test]
+    fn very_wide_report() {
+        let nums = vec!["1".repeat(200), "1".repeat(199) + "0", "0".repeat(200)];
+        let width = ReportError::TooWide { width: 200, max: 128 };
+        assert_eq!(Err(width), gamma_epsilon(&nums, DigitCriteria::default()));
+
+        let ones = (BigUint::from(1u8) << 200u32) - 1u8;
+        let (gamma, epsilon) = gamma_epsilon_big(&nums, DigitCriteria::default()).unwrap();
+        assert_eq!(&ones - 1u8, gamma);
+        assert_eq!(BigUint::from(1u8), epsilon);
+        assert_eq!(
+            (ones, BigUint::zero()),
+            oxygen_co2_big(&nums, DigitCriteria::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn inconsistent_width() {
+        let nums = parse("0101\n011\n1100\n");
+        let err = ReportError::InconsistentWidth { line: 2, expected: 4, found: 3 };
+        assert_eq!(Err(err), report_width(&nums));
+        assert!(gamma_epsilon(&nums, DigitCriteria::default()).is_err());
+        assert!(oxygen_co2(&nums, DigitCriteria::default()).is_err());
+        assert_eq!(
+            Err(ReportError::Empty),
+            power_consumption(&[], DigitCriteria::default())
+        );
+    }
+
+    #[test]
+    fn trie_prefix_counts() {
+        let bits = report_digits(&parse(EXAMPLE), 2).unwrap();
+        let trie = DigitTrie::from_bits(&bits);
+        assert_eq!(12, trie.len());
+        assert_eq!(12, trie.count_prefix(&[]));
+        assert_eq!(7, trie.count_prefix(&[1]));
+        assert_eq!(5, trie.count_prefix(&[0]));
+        assert_eq!(2, trie.count_prefix(&[1, 0, 1, 1]));
+        assert_eq!(1, trie.count_prefix(&[1, 0, 1, 1, 1]));
+        assert_eq!(0, trie.count_prefix(&[1, 1, 0, 1]));
+    }
+
+    #[test]
+    fn bit_criteria() {
+        let nums = parse(EXAMPLE);
+
+        // a 1 needs at least 60% of the votes to be most common
+        let weighted = DigitCriteria::most_common().with_threshold(3, 5);
+        assert_eq!(Ok((4, 27)), gamma_epsilon(&nums, weighted));
+
+        // starting from least common just swaps the pair
+        let least = DigitCriteria::least_common();
+        assert_eq!(Ok((9, 22)), gamma_epsilon(&nums, least));
+        assert_eq!(Ok((10, 23)), oxygen_co2(&nums, least));
+    }
+
+    #[test]
+    fn bit_criteria_ties() {
+        let default = DigitCriteria::default();
+        assert_eq!(1, default.pick(&[2, 2]));
+        assert_eq!(0, default.opposite().pick(&[2, 2]));
+        assert_eq!(0, DigitCriteria::strict_majority().pick(&[2, 2]));
+        assert_eq!(1, DigitCriteria::strict_majority().pick(&[1, 2]));
+        assert_eq!(
+            1,
+            default.ties_to(TieBreak::Lowest).opposite().pick(&[2, 2])
+        );
+
+        let nums = parse("00\n01\n10\n11\n");
+        assert_eq!(Ok((3, 0)), oxygen_co2(&nums, default));
+        assert_eq!(
+            Ok((0, 3)),
+            oxygen_co2(&nums, DigitCriteria::strict_majority())
+        );
+    }
+
+    #[test]
+    fn column_stats() {
+        let nums = parse(EXAMPLE);
+        let report = column_report(&nums, DigitCriteria::default()).unwrap();
+        assert_eq!(ColumnStats { counts: vec![5, 7] }, report.columns[0]);
+        assert_eq!(vec![7, 4, 3, 2, 1], report.oxygen_survivors);
+        assert_eq!(vec![5, 2, 1], report.co2_survivors);
+
+        let stats = &report.columns[0];
+        assert!((stats.balance() - 5.0 / 7.0).abs() < 1e-9);
+        assert!((stats.entropy() - 0.979868756).abs() < 1e-6);
+        assert_eq!(0.0, ColumnStats { counts: vec![0, 3] }.entropy());
+        assert_eq!(1.0, ColumnStats { counts: vec![3, 3] }.entropy());
+
+        let table = report.to_table();
+        assert_eq!(6, table.lines().count());
+        assert_eq!(
+            "  0      5      7    0.714    0.980      7      5",
+            table.lines().nth(1).unwrap()
+        );
+        assert_eq!(
+            "  4      7      5    0.714    0.980      1      -",
+            table.lines().nth(5).unwrap()
+        );
+
+        let json = report.to_json();
+        assert!(json.starts_with(r#"{"columns":[{"zeros":5,"ones":7,"balance":0.714286,"#));
+        assert!(json.ends_with(r#""oxygen_survivors":[7,4,3,2,1],"co2_survivors":[5,2,1]}"#));
+    }
+
+    #[test]
+    fn streaming() {
+        let mut snapshots = vec![];
+        let result = gamma_epsilon_stream(EXAMPLE.as_bytes(), DigitCriteria::default(), 4, |x| {
+            snapshots.push(x)
+        });
+        assert_eq!(Ok((22, 9)), result);
+        assert_eq!(3, snapshots.len());
+        assert_eq!(
+            Snapshot {
+                lines: 4,
+                gamma: 0b10110,
+                epsilon: 0b01001
+            },
+            snapshots[0]
+        );
+        assert_eq!(Snapshot { lines: 12, gamma: 22, epsilon: 9 }, snapshots[2]);
+
+        let file = std::fs::File::open("input/input03.txt").unwrap();
+        let reader = std::io::BufReader::new(file);
+        let (gamma, epsilon) =
+            gamma_epsilon_stream(reader, DigitCriteria::default(), 0, |_| {}).unwrap();
+        assert_eq!(1540244, gamma * epsilon);
+
+        let mut counter = ColumnCounter::new();
+        assert_eq!(
+            Err(ReportError::Empty),
+            counter.snapshot(DigitCriteria::default())
+        );
+        counter.push("101").unwrap();
+        let digit = ReportError::InvalidDigit { line: 2, column: 2, found: 'x' };
+        assert_eq!(Err(digit), counter.push("1x1"));
+        let width = ReportError::InconsistentWidth { line: 2, expected: 3, found: 2 };
+        assert_eq!(Err(width), counter.push("10"));
+        assert_eq!(1, counter.snapshot(DigitCriteria::default()).unwrap().lines);
+    }
+
+    #[test]
+    fn radix() {
+        let nums = parse(EXAMPLE);
+        let default = DigitCriteria::default();
+        assert_eq!(Ok((22, 9)), gamma_epsilon_radix(&nums, 2, default));
+        assert_eq!(Ok((23, 10)), oxygen_co2_radix(&nums, 2, default));
+
+        // ternary: least common ties go to the lowest digit, and 0 is least
+        // common in the last column even though it never appears
+        let nums = parse("012\n112\n102\n221\n");
+        assert_eq!(Ok((14, 0)), gamma_epsilon_radix(&nums, 3, default));
+        assert_eq!(Ok((14, 5)), oxygen_co2_radix(&nums, 3, default));
+        let lowest = default.ties_to(TieBreak::Lowest);
+        assert_eq!(Ok((25, 11)), oxygen_co2_radix(&nums, 3, lowest.opposite()));
+
+        let nums = parse("ff\n0f\nf0\n");
+        assert_eq!(Ok((255, 0x11)), gamma_epsilon_radix(&nums, 16, default));
+        assert_eq!(Ok((255, 15)), oxygen_co2_radix(&nums, 16, default));
+
+        assert_eq!(vec![2, 1, 0], invert_digits(&[0, 1, 2], 3));
+        assert_eq!(128, max_digits(2));
+        assert_eq!(32, max_digits(16));
+        let wide = vec!["f".repeat(33)];
+        let width = ReportError::TooWide { width: 33, max: 32 };
+        assert_eq!(Err(width), gamma_epsilon_radix(&wide, 16, default));
+        let digit = ReportError::InvalidDigit { line: 1, column: 2, found: '3' };
+        assert_eq!(Err(digit), gamma_epsilon_radix(&parse("13\n"), 3, default));
+
+        // wider than a u128 still works as a BigUint
+        let (gamma, _) = gamma_epsilon_radix_big(&wide, 16, default).unwrap();
+        assert_eq!((BigUint::from(1u8) << 132u32) - 1u8, gamma);
+        let nums = parse("ff\n0f\nf0\n");
+        assert_eq!(
+            Ok(BigUint::from(255u32 * 0x11)),
+            power_consumption_radix(&nums, 16, default)
+        );
+        assert_eq!(
+            Ok(BigUint::from(255u32 * 15)),
+            life_support_rating_radix(&nums, 16, default)
+        );
+    }
+
+    #[test]
+    fn radix_threshold() {
+        // 0 is weighted 0.7 and 2 is weighted 0.3, so two 2s lose to one 0
+        let criteria = DigitCriteria::default().with_threshold(7, 10);
+        assert_eq!(0, criteria.pick(&[1, 0, 2]));
+        assert_eq!(2, criteria.pick(&[1, 0, 3]));
+        // 1 is weighted 0.5 either way
+        assert_eq!(1, criteria.pick(&[0, 3, 4]));
+
+        let nums = parse("0\n2\n2\n");
+        assert_eq!(
+            Ok((2, 1)),
+            gamma_epsilon_radix(&nums, 3, DigitCriteria::default())
+        );
+        assert_eq!(Ok((0, 1)), gamma_epsilon_radix(&nums, 3, criteria));
+    }
+
+    #[test]
+    fn exact_threshold() {
+        // 9 ones out of 10 is exactly 9/10, so the tie-break decides
+        let nine_tenths = DigitCriteria::default().with_threshold(9, 10);
+        assert_eq!(1, nine_tenths.pick(&[1, 9]));
+        assert_eq!(0, nine_tenths.ties_to(TieBreak::Lowest).pick(&[1, 9]));
+        assert_eq!(0, nine_tenths.opposite().pick(&[1, 9]));
+        assert_eq!(0, nine_tenths.pick(&[2, 8]));
+
+        let three_fifths = DigitCriteria::default().with_threshold(6, 10);
+        assert_eq!(1, three_fifths.pick(&[4, 6]));
+        assert_eq!(0, three_fifths.ties_to(TieBreak::Lowest).pick(&[4, 6]));
+        assert_eq!(1, three_fifths.ties_to(TieBreak::Lowest).pick(&[3, 7]));
+
+        // in radix 3 at 7/10, three 0s weigh the same as seven 2s
+        let criteria = DigitCriteria::default().with_threshold(7, 10);
+        assert_eq!(2, criteria.pick(&[3, 0, 7]));
+        assert_eq!(0, criteria.ties_to(TieBreak::Lowest).pick(&[3, 0, 7]));
+    }
+
+    #[test]
+    fn radix_report() {
+        let nums = parse("012\n112\n102\n221\n");
+        let report = column_report_radix(&nums, 3, DigitCriteria::default()).unwrap();
+        assert_eq!(ColumnStats { counts: vec![1, 2, 1] }, report.columns[0]);
+        assert_eq!(vec![2, 1], report.oxygen_survivors);
+        assert!((report.columns[0].balance() - 0.5).abs() < 1e-9);
+        assert!((ColumnStats { counts: vec![2, 2, 2] }.entropy() - 1.0).abs() < 1e-9);
+
+        let table = report.to_table();
+        assert_eq!(
+            "pos      0      1      2  balance  entropy     o2    co2",
+            table.lines().next().unwrap()
+        );
+        let json = report.to_json();
+        assert!(json.starts_with(r#"{"columns":[{"counts":[1,2,1],"balance":0.500000,"#));
+    }
+
+    #[test]
+    fn edge_cases() {
+        let default = DigitCriteria::default();
+        assert_eq!(Err(ReportError::Empty), oxygen_co2(&[], default));
+        assert_eq!(Err(ReportError::Empty), gamma_epsilon(&[], default));
+
+        let digit = ReportError::InvalidDigit { line: 2, column: 3, found: '2' };
+        let nums = parse("0101\n0121\n");
+        assert_eq!(Err(digit.clone()), gamma_epsilon(&nums, default));
+        assert_eq!(Err(digit), oxygen_co2(&nums, default));
+
+        // two identical readings can't be told apart by any bit
+        let nums = parse("0101\n0101\n0011\n");
+        let unresolved = ReportError::Unresolved { rating: "oxygen", survivors: 2 };
+        assert_eq!(Err(unresolved), oxygen_co2(&nums, default));
+        assert_eq!(Ok((5, 10)), gamma_epsilon(&nums, default));
+
+        let nums = parse("0000\n0000\n1111\n1110\n");
+        let unresolved = ReportError::Unresolved { rating: "co2", survivors: 2 };
+        assert_eq!(Err(unresolved), oxygen_co2(&nums, default));
+
+        assert_eq!(Ok((5, 5)), oxygen_co2(&parse("0101\n"), default));
+    }
+
+    // The original list-filtering version of the rating search, used as a
+    // reference. Keeps readings matching the picked bit until one is left.
+    fn filter_rating(nums: &[String], criteria: DigitCriteria) -> Option<u128> {
+        let mut xs = report_digits(nums, 2).unwrap();
+        let mut bit = 0;
+        while xs.len() > 1 && bit < xs[0].len() {
+            let ones = xs.iter().filter(|x| x[bit] == 1).count();
+            let keep = match (ones, xs.len() - ones) {
+                (0, _) => 0,
+                (_, 0) => 1,
+                (ones, zeros) => criteria.pick(&[zeros, ones]),
+            };
+            xs.retain(|x| x[bit] == keep);
+            bit += 1;
+        }
+        match xs.len() {
+            1 => Some(from_bits(&xs[0])),
+            _ => None,
+        }
+    }
+
+    // Random reports of 1 to 40 readings, all the same width
+    fn report() -> impl Strategy<Value = Vec<String>> {
+        (1..20usize).prop_flat_map(|width| {
+            let line = proptest::string::string_regex(&format!("[01]{{{}}}", width)).unwrap();
+            prop::collection::vec(line, 1..40)
+        })
+    }
+
+    fn criteria() -> impl Strategy<Value = DigitCriteria> {
+        let threshold = (1..=20u32).prop_flat_map(|q| (0..q, Just(q)));
+        (any::<bool>(), any::<bool>(), threshold).prop_map(|(least, tie_zero, (p, q))| {
+            let tie = if tie_zero {
+                TieBreak::Lowest
+            } else {
+                TieBreak::Highest
+            };
+            let criteria = DigitCriteria::default().ties_to(tie).with_threshold(p, q);
+            if least {
+                criteria.opposite()
+            } else {
+                criteria
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_gamma_epsilon_complement(nums in report()) {
+            let width = nums[0].len();
+            let (gamma, epsilon) = gamma_epsilon(&nums, DigitCriteria::default()).unwrap();
+            prop_assert_eq!((1u128 << width) - 1, gamma + epsilon);
+        }
+
+        #[test]
+        fn prop_ratings_match_filtering(nums in report(), criteria in criteria()) {
+            let expected = (
+                filter_rating(&nums, criteria),
+                filter_rating(&nums, criteria.opposite()),
+            );
+            match oxygen_co2(&nums, criteria) {
+                Ok((oxygen, co2)) => {
+                    prop_assert_eq!((Some(oxygen), Some(co2)), expected);
+                    let width = nums[0].len();
+                    let readings: Vec<u128> = nums.iter().map(|x| u128::from_str_radix(x, 2).unwrap()).collect();
+                    prop_assert!(readings.contains(&oxygen) && readings.contains(&co2));
+                    prop_assert!(oxygen < 1 << width && co2 < 1 << width);
+                }
+                Err(ReportError::Unresolved { .. }) => {
+                    prop_assert!(expected.0.is_none() || expected.1.is_none());
+                }
+                Err(e) => prop_assert!(false, "unexpected error {:?}", e),
+            }
+        }
+
+        #[test]
+        fn prop_garbage_never_panics(nums in prop::collection::vec("[012 ]{0,6}", 0..10)) {
+            let _ = gamma_epsilon(&nums, DigitCriteria::default());
+            let _ = oxygen_co2(&nums, DigitCriteria::default());
+            let _ = life_support_rating(&nums, DigitCriteria::default());
+        }
+    }
 }
@@ -4,9 +4,17 @@
 #![allow(dead_code)]
 
 use indoc::indoc;
-use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::read_to_string;
 
+pub mod events;
+pub mod generator;
+pub mod montecarlo;
+mod rng;
+pub mod server;
+pub mod solver;
+
 const EXAMPLE: &str = indoc! {"
     7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
 
@@ -29,101 +37,636 @@ const EXAMPLE: &str = indoc! {"
      2  0 12  3  7
 "};
 
-// maybe make this a struct so I can add getters?
-type Board = Vec<usize>;
+// One way to win. Each shape expands into lines (sets of cells) for a given
+// board size, and completing any one of them wins.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rows,
+    Columns,
+    // either corner-to-corner diagonal, square boards only
+    Diagonals,
+    FourCorners,
+    // both diagonals at once, square boards only
+    X,
+    // the whole card
+    Blackout,
+    // an ASCII mask, which wins wherever it fits on the board
+    Custom(Mask),
+}
 
-fn to_int(x: &str) -> usize {
-    x.parse().unwrap()
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    rows: usize,
+    cols: usize,
+    cells: Vec<bool>,
 }
 
-// String --> (nums, boards)
-// nums: Vec<int>
-// board: Vec<int> (fixed size 5x5)
-fn parse(input: &str) -> (Vec<usize>, Vec<Board>) {
-    let blank_line = Regex::new(r"\n\n").expect("Invalid regex");
-    let blocks: Vec<_> = blank_line.split(&input).into_iter().collect();
-    let nums: Vec<usize> = blocks[0].split(",").into_iter().map(to_int).collect();
+impl Mask {
+    // Rows of '#' or 'X' for cells that must be marked, '.' for the rest
+    pub fn parse(ascii: &str) -> Result<Mask, ParseError> {
+        let rows: Vec<&str> = ascii
+            .lines()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect();
+        let cols = rows.first().ok_or(ParseError::EmptyMask)?.chars().count();
+        let mut cells = vec![];
+        for (i, row) in rows.iter().enumerate() {
+            if row.chars().count() != cols {
+                return Err(ParseError::BadMask { line: i + 1, text: row.to_string() });
+            }
+            for c in row.chars() {
+                match c {
+                    '#' | 'X' | 'x' => cells.push(true),
+                    '.' => cells.push(false),
+                    _ => return Err(ParseError::BadMask { line: i + 1, text: row.to_string() }),
+                }
+            }
+        }
+        if !cells.contains(&true) {
+            return Err(ParseError::EmptyMask);
+        }
+        Ok(Mask { rows: rows.len(), cols, cells })
+    }
+}
+
+// Which line of a pattern was completed
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineKind {
+    Row(usize),
+    Column(usize),
+    Diagonal,
+    AntiDiagonal,
+    FourCorners,
+    X,
+    Blackout,
+    // index of the mask in the pattern, and where its top left corner sits
+    Custom { mask: usize, row: usize, col: usize },
+}
 
-    let boards: Vec<Board> = blocks
-        .into_iter()
-        .skip(1) // skip the first block of nums
-        .map(|blk| blk.split_whitespace().map(to_int).collect())
-        .collect();
+impl fmt::Display for LineKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LineKind::Row(r) => write!(f, "row {}", r),
+            LineKind::Column(c) => write!(f, "column {}", c),
+            LineKind::Diagonal => write!(f, "diagonal"),
+            LineKind::AntiDiagonal => write!(f, "anti-diagonal"),
+            LineKind::FourCorners => write!(f, "four corners"),
+            LineKind::X => write!(f, "x"),
+            LineKind::Blackout => write!(f, "blackout"),
+            LineKind::Custom { mask, row, col } => write!(f, "mask {} at {},{}", mask, row, col),
+        }
+    }
+}
 
-    return (nums, boards);
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    kind: LineKind,
+    cells: Vec<usize>,
 }
 
-fn load_input(filename: &str) -> (Vec<usize>, Vec<Board>) {
-    let input = read_to_string(&filename).unwrap();
-    parse(&input)
+impl Line {
+    fn new(kind: LineKind, mut cells: Vec<usize>) -> Self {
+        cells.sort_unstable();
+        cells.dedup();
+        Line { kind, cells }
+    }
 }
 
-const MARKED: usize = 999999;
+// The set of shapes that win a game. The default is the puzzle's rule: any
+// complete row or column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinPattern {
+    shapes: Vec<Shape>,
+}
 
-// Check if this board is complete, called numbers have been MARKED already.
-// Boards are 5x5, check rows and cols only, no diagonals.
-fn is_winner(board: &Board) -> bool {
-    // check rows
-    for i in 0..5 {
-        let slice = &board[i * 5..i * 5 + 5];
-        if slice.into_iter().all(|&x| x == MARKED) {
-            return true;
+impl Default for WinPattern {
+    fn default() -> Self {
+        WinPattern {
+            shapes: vec![Shape::Rows, Shape::Columns],
         }
     }
+}
+
+impl WinPattern {
+    pub fn new(shapes: Vec<Shape>) -> Self {
+        WinPattern { shapes }
+    }
 
-    // check cols
-    for i in 0..5 {
-        let slice = &board[i..];
-        if slice.into_iter().step_by(5).all(|&x| x == MARKED) {
-            return true;
+    // Comma separated shapes, as given on the command line: rows, columns,
+    // diagonals, corners, x, blackout, or a mask with '/' between its rows
+    // such as "#.#/.#./#.#". Diagonals and x need square boards, and masks a
+    // board at least their size; check() finds boards they don't fit.
+    pub fn parse(spec: &str) -> Result<WinPattern, ParseError> {
+        let mut shapes = vec![];
+        for item in spec.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            shapes.push(match item.to_ascii_lowercase().as_str() {
+                "rows" => Shape::Rows,
+                "columns" | "cols" => Shape::Columns,
+                "diagonals" => Shape::Diagonals,
+                "corners" | "four-corners" => Shape::FourCorners,
+                "x" => Shape::X,
+                "blackout" => Shape::Blackout,
+                _ if item.contains(['#', '.', '/']) => {
+                    Shape::Custom(Mask::parse(&item.replace('/', "\n"))?)
+                }
+                _ => return Err(ParseError::UnknownShape { text: item.to_string() }),
+            });
         }
+        if shapes.is_empty() {
+            return Err(ParseError::NoShapes);
+        }
+        Ok(WinPattern::new(shapes))
     }
 
-    return false;
+    // Every shape has to have at least one line on every board. Otherwise
+    // it can never win there, e.g. diagonals on a 3x4 board.
+    pub fn check(&self, boards: &[Board]) -> Result<(), ParseError> {
+        for (i, board) in boards.iter().enumerate() {
+            for (j, shape) in self.shapes.iter().enumerate() {
+                let alone = WinPattern::new(vec![shape.clone()]);
+                if alone.lines(board.rows, board.cols).is_empty() {
+                    return Err(ParseError::ShapeWontFit {
+                        board: i,
+                        shape: j,
+                        rows: board.rows,
+                        cols: board.cols,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // All the winning lines on a rows x cols board
+    fn lines(&self, rows: usize, cols: usize) -> Vec<Line> {
+        let at = |r: usize, c: usize| r * cols + c;
+        let square = rows == cols;
+        let diagonal = || (0..rows).map(|i| at(i, i)).collect::<Vec<_>>();
+        let anti_diagonal = || (0..rows).map(|i| at(i, cols - 1 - i)).collect::<Vec<_>>();
+
+        let mut lines = vec![];
+        let mut masks = 0;
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rows => {
+                    for r in 0..rows {
+                        lines.push(Line::new(
+                            LineKind::Row(r),
+                            (0..cols).map(|c| at(r, c)).collect(),
+                        ));
+                    }
+                }
+                Shape::Columns => {
+                    for c in 0..cols {
+                        lines.push(Line::new(
+                            LineKind::Column(c),
+                            (0..rows).map(|r| at(r, c)).collect(),
+                        ));
+                    }
+                }
+                Shape::Diagonals if square => {
+                    lines.push(Line::new(LineKind::Diagonal, diagonal()));
+                    lines.push(Line::new(LineKind::AntiDiagonal, anti_diagonal()));
+                }
+                Shape::X if square => {
+                    lines.push(Line::new(
+                        LineKind::X,
+                        [diagonal(), anti_diagonal()].concat(),
+                    ));
+                }
+                Shape::Diagonals | Shape::X => {}
+                Shape::FourCorners => {
+                    let corners = vec![
+                        at(0, 0),
+                        at(0, cols - 1),
+                        at(rows - 1, 0),
+                        at(rows - 1, cols - 1),
+                    ];
+                    lines.push(Line::new(LineKind::FourCorners, corners));
+                }
+                Shape::Blackout => {
+                    lines.push(Line::new(LineKind::Blackout, (0..rows * cols).collect()));
+                }
+                Shape::Custom(mask) => {
+                    // try every position where the mask fits on the board
+                    for r in 0..(rows + 1).saturating_sub(mask.rows) {
+                        for c in 0..(cols + 1).saturating_sub(mask.cols) {
+                            let cells = (0..mask.rows * mask.cols)
+                                .filter(|&i| mask.cells[i])
+                                .map(|i| at(r + i / mask.cols, c + i % mask.cols))
+                                .collect();
+                            let kind = LineKind::Custom { mask: masks, row: r, col: c };
+                            lines.push(Line::new(kind, cells));
+                        }
+                    }
+                    masks += 1;
+                }
+            }
+        }
+        lines
+    }
 }
 
-// (boards, all_nums) --> (winner_remaining_squares, called_nums, index_of_winner)
-fn first_winner(boards: &Vec<Board>, nums: &Vec<usize>) -> (Board, Vec<usize>, usize) {
-    for i in 1..nums.len() {
-        let called_nums = &nums[0..i];
+// A rows x cols bingo board. Marked cells are kept in a bitmask, with a hit
+// counter per winning line so marking a number can tell straight away if it won.
+#[derive(Debug, Clone)]
+pub struct Board {
+    rows: usize,
+    cols: usize,
+    numbers: Vec<usize>,
+    // number --> cell indexes, so marking doesn't have to search the board.
+    // A number can be on a board more than once, and calling it marks every copy.
+    cells: HashMap<usize, Vec<usize>>,
+    // one bit per cell, 64 cells per word
+    marked: Vec<u64>,
+    lines: Vec<Line>,
+    line_hits: Vec<usize>,
+    // cell --> indexes of the lines it's part of
+    cell_lines: Vec<Vec<usize>>,
+}
 
-        for (i, board) in boards.iter().enumerate() {
-            let marked_board: Board = board
-                .into_iter()
-                .map(|&x| if called_nums.contains(&x) { MARKED } else { x })
-                .collect();
-
-            if is_winner(&marked_board) {
-                let unmarked_squares: Board = marked_board
-                    .clone()
-                    .into_iter()
-                    .filter(|&x| x != MARKED)
-                    .collect();
-                return (unmarked_squares, called_nums.to_vec(), i);
+impl Board {
+    // A board that wins on the default pattern, rows and columns
+    fn new(rows: usize, cols: usize, numbers: Vec<usize>) -> Self {
+        assert_eq!(rows * cols, numbers.len(), "board is not {}x{}", rows, cols);
+        let mut cells: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, &n) in numbers.iter().enumerate() {
+            cells.entry(n).or_default().push(i);
+        }
+        let mut board = Board {
+            rows,
+            cols,
+            numbers,
+            cells,
+            marked: vec![0; (rows * cols).div_ceil(64)],
+            lines: vec![],
+            line_hits: vec![],
+            cell_lines: vec![],
+        };
+        board.set_pattern(&WinPattern::default());
+        board
+    }
+
+    fn set_pattern(&mut self, pattern: &WinPattern) {
+        self.lines = pattern.lines(self.rows, self.cols);
+        self.cell_lines = vec![vec![]; self.numbers.len()];
+        for (i, line) in self.lines.iter().enumerate() {
+            for &cell in &line.cells {
+                self.cell_lines[cell].push(i);
+            }
+        }
+        // count any marks already on the board
+        self.line_hits = self
+            .lines
+            .iter()
+            .map(|line| line.cells.iter().filter(|&&c| self.is_marked(c)).count())
+            .collect();
+    }
+
+    // Mark n if it's on the board. Returns the index of the line it completed, if any.
+    fn mark(&mut self, n: usize) -> Option<usize> {
+        let (_, completed) = self.mark_cells(n)?;
+        completed.first().copied()
+    }
+
+    // Mark every copy of n on the board that isn't marked yet. Returns the cells
+    // marked, and the indexes of every line that completed.
+    fn mark_cells(&mut self, n: usize) -> Option<(Vec<usize>, Vec<usize>)> {
+        let cells: Vec<usize> = self
+            .cells
+            .get(&n)?
+            .iter()
+            .copied()
+            .filter(|&c| !self.is_marked(c))
+            .collect();
+        if cells.is_empty() {
+            return None;
+        }
+
+        let mut completed = vec![];
+        for &cell in &cells {
+            self.marked[cell / 64] |= 1 << (cell % 64);
+            for &i in &self.cell_lines[cell] {
+                self.line_hits[i] += 1;
+                if self.line_hits[i] == self.lines[i].cells.len() {
+                    completed.push(i);
+                }
+            }
+        }
+        Some((cells, completed))
+    }
+
+    fn is_marked(&self, cell: usize) -> bool {
+        self.marked[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    fn is_winner(&self) -> bool {
+        self.lines
+            .iter()
+            .zip(&self.line_hits)
+            .any(|(line, &hits)| hits == line.cells.len())
+    }
+
+    fn unmarked_sum(&self) -> usize {
+        (0..self.numbers.len())
+            .filter(|&i| !self.is_marked(i))
+            .map(|i| self.numbers[i])
+            .sum()
+    }
+
+    fn score(&self, last_called: usize) -> usize {
+        self.unmarked_sum() * last_called
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    NoNumbers,
+    BadNumber {
+        line: usize,
+        text: String,
+    },
+    // a board row with a different number of columns than the first row
+    RaggedBoard {
+        board: usize,
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    BadMask {
+        line: usize,
+        text: String,
+    },
+    EmptyMask,
+    // WinPattern::parse() items that are neither a shape nor a mask
+    UnknownShape {
+        text: String,
+    },
+    NoShapes,
+    // the pattern's shape (an index) has no lines on the board
+    ShapeWontFit {
+        board: usize,
+        shape: usize,
+        rows: usize,
+        cols: usize,
+    },
+    // parse_board() wants exactly one board
+    NoBoard,
+    ExtraBoards {
+        count: usize,
+    },
+}
+
+fn to_int(x: &str, line: usize) -> Result<usize, ParseError> {
+    x.parse()
+        .map_err(|_| ParseError::BadNumber { line, text: x.to_string() })
+}
+
+// String --> (nums, boards)
+// nums: the first line, comma separated
+// boards: blocks of rows separated by blank lines. Each board's size comes
+// from its own layout, so boards of different sizes can be mixed.
+pub fn parse(input: &str) -> Result<(Vec<usize>, Vec<Board>), ParseError> {
+    let mut lines = input.lines().enumerate().map(|(i, x)| (i + 1, x.trim()));
+
+    let (line, first) = lines
+        .find(|(_, x)| !x.is_empty())
+        .ok_or(ParseError::NoNumbers)?;
+    let nums = first
+        .split(',')
+        .map(|x| to_int(x.trim(), line))
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    Ok((nums, parse_boards(lines)?))
+}
+
+// Blocks of rows separated by blank lines, from (line number, text) pairs
+fn parse_boards<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<Board>, ParseError> {
+    let mut boards = vec![];
+    let mut rows: Vec<Vec<usize>> = vec![];
+    for (line, x) in lines.chain([(0, "")]) {
+        if x.is_empty() {
+            // blank line (or the end of input) finishes the current board
+            if !rows.is_empty() {
+                let cols = rows[0].len();
+                boards.push(Board::new(rows.len(), cols, rows.concat()));
+                rows.clear();
+            }
+            continue;
+        }
+
+        let row = x
+            .split_whitespace()
+            .map(|n| to_int(n, line))
+            .collect::<Result<Vec<usize>, _>>()?;
+        if let Some(first_row) = rows.first() {
+            if row.len() != first_row.len() {
+                return Err(ParseError::RaggedBoard {
+                    board: boards.len(),
+                    line,
+                    expected: first_row.len(),
+                    found: row.len(),
+                });
+            }
+        }
+        rows.push(row);
+    }
+
+    Ok(boards)
+}
+
+// A single board on its own, e.g. one sent by a player
+pub fn parse_board(input: &str) -> Result<Board, ParseError> {
+    let lines = input.lines().enumerate().map(|(i, x)| (i + 1, x.trim()));
+    let mut boards = parse_boards(lines)?;
+    match boards.len() {
+        1 => Ok(boards.remove(0)),
+        0 => Err(ParseError::NoBoard),
+        _ => Err(ParseError::ExtraBoards { count: boards.len() }),
+    }
+}
+
+fn load_input(filename: &str) -> (Vec<usize>, Vec<Board>) {
+    let input = read_to_string(filename).unwrap();
+    parse(&input).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Win {
+    board: usize,
+    // index into the called numbers
+    turn: usize,
+    number: usize,
+    score: usize,
+    // the line that won it
+    line: Line,
+}
+
+// Call the numbers in order, marking every board, and collect the wins as they happen.
+// Each board wins at most once.
+fn play(boards: &[Board], nums: &[usize], pattern: &WinPattern) -> Vec<Win> {
+    let mut boards = boards.to_vec();
+    for board in boards.iter_mut() {
+        board.set_pattern(pattern);
+    }
+    let mut won = vec![false; boards.len()];
+    let mut wins = vec![];
+
+    for (turn, &n) in nums.iter().enumerate() {
+        for (i, board) in boards.iter_mut().enumerate() {
+            if won[i] {
+                continue;
+            }
+            if let Some(line) = board.mark(n) {
+                won[i] = true;
+                let line = board.lines[line].clone();
+                wins.push(Win {
+                    board: i,
+                    turn,
+                    number: n,
+                    score: board.score(n),
+                    line,
+                });
             }
         }
+        if wins.len() == boards.len() {
+            break;
+        }
     }
 
-    // Not reachable with given input (there's always a winner)
-    return (vec![], [].to_vec(), 0);
+    wins
+}
+
+// Which board counts as the winner when several win on the same number
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TiePolicy {
+    // the one that comes first in the input
+    BoardOrder,
+    // highest score, then board order
+    HighestScore,
+    // lowest score, then board order
+    LowestScore,
 }
 
-fn last_winner(boards: &Vec<Board>, nums: &Vec<usize>) -> (Board, Vec<usize>) {
-    // find and remove the first winner until only one board left standing
-    let mut remaining = boards.clone();
-    while remaining.len() > 1 {
-        let (_, _, i) = first_winner(&remaining, &nums);
-        remaining.remove(i);
+impl TiePolicy {
+    fn pick<'a>(&self, wins: &'a [Win]) -> Option<&'a Win> {
+        // min_by_key/max_by_key keep the first/last of equal keys, so reverse
+        // the board order for max to stay on the earliest board
+        match self {
+            TiePolicy::BoardOrder => wins.iter().min_by_key(|w| w.board),
+            TiePolicy::HighestScore => wins.iter().rev().max_by_key(|w| w.score),
+            TiePolicy::LowestScore => wins.iter().min_by_key(|w| w.score),
+        }
     }
+}
 
-    // last board remaining is the winner, but still need to find the numbers
-    // called to make it a winner
-    let (winner, nums_called, _) = first_winner(&remaining, &nums);
-    return (winner, nums_called);
+fn first_winner(
+    boards: &[Board],
+    nums: &[usize],
+    pattern: &WinPattern,
+    tie: TiePolicy,
+) -> Option<Win> {
+    rank(boards, nums, pattern).first_by(tie).cloned()
 }
 
-fn score(board: &Board, nums: &Vec<usize>) -> usize {
-    board.into_iter().sum::<usize>() * nums.last().unwrap()
+// The last board to win. Boards that never win are left out.
+fn last_winner(
+    boards: &[Board],
+    nums: &[usize],
+    pattern: &WinPattern,
+    tie: TiePolicy,
+) -> Option<Win> {
+    rank(boards, nums, pattern).last_by(tie).cloned()
+}
+
+// Every board's win, found without playing the game. With each number's call
+// index, a line completes when its last number is called, i.e. at the max
+// call index of its cells, and a board wins on the first of its lines to complete.
+#[derive(Debug)]
+struct Ranking {
+    // in the order they win, boards winning on the same turn in board order
+    wins: Vec<Win>,
+    // boards that never win with these numbers
+    never: Vec<usize>,
+}
+
+impl Ranking {
+    fn first(&self) -> Option<&Win> {
+        self.wins.first()
+    }
+
+    fn last(&self) -> Option<&Win> {
+        self.wins.last()
+    }
+
+    // k-th board to win, from 0
+    fn nth(&self, k: usize) -> Option<&Win> {
+        self.wins.get(k)
+    }
+
+    // All the boards that won on each winning turn, in turn order
+    fn by_turn(&self) -> Vec<&[Win]> {
+        self.wins.chunk_by(|a, b| a.turn == b.turn).collect()
+    }
+
+    // The first turn with a winner, settling any tie with the policy
+    fn first_by(&self, tie: TiePolicy) -> Option<&Win> {
+        self.by_turn().first().and_then(|wins| tie.pick(wins))
+    }
+
+    // The last turn with a winner, settling any tie with the policy
+    fn last_by(&self, tie: TiePolicy) -> Option<&Win> {
+        self.by_turn().last().and_then(|wins| tie.pick(wins))
+    }
+}
+
+fn rank(boards: &[Board], nums: &[usize], pattern: &WinPattern) -> Ranking {
+    // number --> index of its first call
+    let mut called: HashMap<usize, usize> = HashMap::new();
+    for (turn, &n) in nums.iter().enumerate() {
+        called.entry(n).or_insert(turn);
+    }
+
+    let mut wins = vec![];
+    let mut never = vec![];
+    for (i, board) in boards.iter().enumerate() {
+        let turn_of = |cell: usize| called.get(&board.numbers[cell]).copied();
+
+        // (turn, line) of the first line to complete. Lines with an uncalled
+        // number never complete.
+        let best = pattern
+            .lines(board.rows, board.cols)
+            .into_iter()
+            .filter_map(|line| {
+                let turns: Option<Vec<usize>> = line.cells.iter().map(|&c| turn_of(c)).collect();
+                turns.map(|t| (t.into_iter().max().unwrap_or(0), line))
+            })
+            .min_by_key(|(turn, _)| *turn);
+
+        match best {
+            Some((turn, line)) => {
+                let unmarked: usize = (0..board.numbers.len())
+                    .filter(|&c| turn_of(c).is_none_or(|t| t > turn))
+                    .map(|c| board.numbers[c])
+                    .sum();
+                let number = nums[turn];
+                wins.push(Win {
+                    board: i,
+                    turn,
+                    number,
+                    score: unmarked * number,
+                    line,
+                });
+            }
+            None => never.push(i),
+        }
+    }
+
+    wins.sort_by_key(|w| (w.turn, w.board));
+    Ranking { wins, never }
 }
 
 #[cfg(test)]
@@ -132,22 +675,34 @@ mod tests {
 
     #[test]
     fn example_1() {
-        let (nums, boards) = parse(EXAMPLE);
+        let (nums, boards) = parse(EXAMPLE).unwrap();
         assert_eq!(27, nums.len());
         assert_eq!(3, boards.len());
 
-        let (board, called_nums, index) = first_winner(&boards, &nums);
-        let score = score(&board, &called_nums);
-        assert_eq!(2, index);
-        assert_eq!(4512, score);
+        let winner = first_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!(2, winner.board);
+        assert_eq!(24, winner.number);
+        assert_eq!(4512, winner.score);
     }
 
     #[test]
     fn example_2() {
-        let (nums, boards) = parse(EXAMPLE);
-        let (board, called_nums) = last_winner(&boards, &nums);
-        let score = score(&board, &called_nums);
-        assert_eq!(1924, score);
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let winner = last_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!(1, winner.board);
+        assert_eq!(1924, winner.score);
     }
 
     #[test]
@@ -156,16 +711,287 @@ mod tests {
         assert_eq!(100, nums.len());
         assert_eq!(100, boards.len());
 
-        let (board, called_nums, _) = first_winner(&boards, &nums);
-        let score = score(&board, &called_nums);
-        assert_eq!(16674, score);
+        let winner = first_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!(16674, winner.score);
     }
 
     #[test]
     fn part_2() {
         let (nums, boards) = load_input("input/input04.txt");
-        let (board, called_nums) = last_winner(&boards, &nums);
-        let score = score(&board, &called_nums);
-        assert_eq!(7075, score);
+        let winner = last_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!(7075, winner.score);
+    }
+
+    #[test]
+    fn board_marking() {
+        // 999999 used to be the sentinel for a marked cell
+        let mut board = Board::new(5, 5, (999990..1000015).collect());
+        assert_eq!(None, board.mark(7));
+        for n in [999999, 999994, 1000004, 1000009] {
+            assert_eq!(None, board.mark(n));
+        }
+        assert_eq!(None, board.mark(999999));
+        assert!(!board.is_winner());
+        assert_eq!(Some(9), board.mark(1000014));
+        assert!(board.is_winner());
+
+        let total: usize = (999990..1000015).sum();
+        let marked = 999999 + 999994 + 1000004 + 1000009 + 1000014;
+        assert_eq!(total - marked, board.unmarked_sum());
+        assert_eq!((total - marked) * 2, board.score(2));
+    }
+
+    #[test]
+    fn board_sizes() {
+        let input = indoc! {"
+            5,1,4,2,8,10,12
+
+            1 2 3
+            4 5 6
+            7 8 9
+
+            10 11
+            12 13
+            14 15
+            16 17
+        "};
+        let (nums, boards) = parse(input).unwrap();
+        assert_eq!((3, 3), (boards[0].rows, boards[0].cols));
+        assert_eq!((4, 2), (boards[1].rows, boards[1].cols));
+
+        // the 3x3 board completes its middle column with the 5th number
+        let winner = first_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!((0, 4), (winner.board, winner.turn));
+        assert_eq!((3 + 6 + 7 + 9) * 8, winner.score);
+
+        // a 64+ cell board needs more than one word of marks
+        let mut big = Board::new(9, 9, (0..81).collect());
+        for n in (0..81).step_by(9) {
+            assert_eq!(n == 72, big.mark(n).is_some());
+        }
+        assert!(big.is_marked(72) && !big.is_marked(73));
+        assert_eq!(
+            (0..81).sum::<usize>() - (0..81).step_by(9).sum::<usize>(),
+            big.unmarked_sum()
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let ragged = "1,2\n\n1 2 3\n4 5 6\n\n1 2\n3 4 5\n";
+        let err = ParseError::RaggedBoard {
+            board: 1,
+            line: 7,
+            expected: 2,
+            found: 3,
+        };
+        assert_eq!(Err(err), parse(ragged).map(|_| ()));
+
+        let bad = ParseError::BadNumber { line: 3, text: "x".to_string() };
+        assert_eq!(Err(bad), parse("1,2\n\n1 x\n").map(|_| ()));
+        assert_eq!(Err(ParseError::NoNumbers), parse("\n\n").map(|_| ()));
+
+        let board = parse_board("\n1 2\n3 4\n").unwrap();
+        assert_eq!(
+            (2, 2, vec![1, 2, 3, 4]),
+            (board.rows, board.cols, board.numbers)
+        );
+        assert_eq!(Err(ParseError::NoBoard), parse_board(" \n").map(|_| ()));
+        let two = ParseError::ExtraBoards { count: 2 };
+        assert_eq!(Err(two), parse_board("1\n\n2\n").map(|_| ()));
+    }
+
+    #[test]
+    fn win_patterns() {
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let winner = first_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        )
+        .unwrap();
+        assert_eq!(LineKind::Row(0), winner.line.kind);
+        assert_eq!(vec![0, 1, 2, 3, 4], winner.line.cells);
+
+        let lines = |shape: Shape| WinPattern::new(vec![shape]).lines(5, 5);
+        assert_eq!(vec![0, 6, 12, 18, 24], lines(Shape::Diagonals)[0].cells);
+        assert_eq!(vec![4, 8, 12, 16, 20], lines(Shape::Diagonals)[1].cells);
+        assert_eq!(vec![0, 4, 20, 24], lines(Shape::FourCorners)[0].cells);
+        assert_eq!(9, lines(Shape::X)[0].cells.len());
+        assert_eq!(25, lines(Shape::Blackout)[0].cells.len());
+        assert!(WinPattern::new(vec![Shape::Diagonals])
+            .lines(3, 4)
+            .is_empty());
+
+        // a 2x2 square fits in 16 places on a 5x5 board
+        let square = Mask::parse("##\n##").unwrap();
+        let squares = lines(Shape::Custom(square));
+        assert_eq!(16, squares.len());
+        assert_eq!(
+            LineKind::Custom { mask: 0, row: 3, col: 3 },
+            squares[15].kind
+        );
+        assert_eq!(vec![18, 19, 23, 24], squares[15].cells);
+
+        // board 2's corners are 14, 4, 2, 7, all called by 14
+        let corners = WinPattern::new(vec![Shape::FourCorners]);
+        let winner = first_winner(&boards, &nums, &corners, TiePolicy::BoardOrder).unwrap();
+        assert_eq!((2, 9, 14), (winner.board, winner.turn, winner.number));
+        assert_eq!(LineKind::FourCorners, winner.line.kind);
+
+        let blackout = WinPattern::new(vec![Shape::Blackout]);
+        let winner = last_winner(&boards, &nums, &blackout, TiePolicy::BoardOrder).unwrap();
+        assert_eq!(0, winner.score);
+    }
+
+    #[test]
+    fn mask_errors() {
+        let plus = Mask::parse(".#.\n###\n.#.\n").unwrap();
+        assert_eq!((3, 3), (plus.rows, plus.cols));
+        assert_eq!(5, plus.cells.iter().filter(|&&x| x).count());
+
+        let err = ParseError::BadMask { line: 2, text: "##".to_string() };
+        assert_eq!(Err(err), Mask::parse("#.#\n##\n"));
+        let err = ParseError::BadMask { line: 1, text: "#?".to_string() };
+        assert_eq!(Err(err), Mask::parse("#?"));
+        assert_eq!(Err(ParseError::EmptyMask), Mask::parse("..\n.."));
+        assert_eq!(Err(ParseError::EmptyMask), Mask::parse(""));
+    }
+
+    #[test]
+    fn pattern_specs() {
+        let plus = Mask::parse(".#.\n###\n.#.").unwrap();
+        let shapes = vec![Shape::Rows, Shape::FourCorners, Shape::Custom(plus)];
+        assert_eq!(
+            Ok(WinPattern::new(shapes)),
+            WinPattern::parse("rows, Corners,.#./###/.#.")
+        );
+        assert_eq!(Ok(WinPattern::default()), WinPattern::parse("rows,columns"));
+
+        let err = ParseError::UnknownShape { text: "rwos".to_string() };
+        assert_eq!(Err(err), WinPattern::parse("rwos"));
+        let err = ParseError::BadMask { line: 2, text: "#".to_string() };
+        assert_eq!(Err(err), WinPattern::parse("x,##/#"));
+        assert_eq!(Err(ParseError::NoShapes), WinPattern::parse(" , "));
+
+        let square = parse_board("1 2\n3 4").unwrap();
+        let wide = parse_board("1 2 3\n4 5 6").unwrap();
+        let diagonals = WinPattern::parse("rows,diagonals").unwrap();
+        assert_eq!(Ok(()), diagonals.check(std::slice::from_ref(&square)));
+        let err = ParseError::ShapeWontFit { board: 1, shape: 1, rows: 2, cols: 3 };
+        assert_eq!(Err(err), diagonals.check(&[square.clone(), wide.clone()]));
+        let big = WinPattern::parse("###").unwrap();
+        assert_eq!(Ok(()), big.check(&[wide]));
+        let err = ParseError::ShapeWontFit { board: 0, shape: 0, rows: 2, cols: 2 };
+        assert_eq!(Err(err), big.check(&[square]));
+    }
+
+    #[test]
+    fn ranking() {
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let ranking = rank(&boards, &nums, &WinPattern::default());
+        assert_eq!(
+            vec![2, 0, 1],
+            ranking.wins.iter().map(|w| w.board).collect::<Vec<_>>()
+        );
+        assert_eq!(4512, ranking.first().unwrap().score);
+        assert_eq!(1924, ranking.last().unwrap().score);
+        assert_eq!(Some(0), ranking.nth(1).map(|w| w.board));
+        assert_eq!(None, ranking.nth(3));
+        assert!(ranking.never.is_empty());
+
+        // stop calling before the last board wins
+        let ranking = rank(&boards, &nums[..14], &WinPattern::default());
+        assert_eq!(vec![1], ranking.never);
+
+        // same wins, same order, same lines as playing the game
+        let (nums, boards) = load_input("input/input04.txt");
+        let x = WinPattern::new(vec![Shape::Rows, Shape::Columns, Shape::X]);
+        for pattern in [WinPattern::default(), x] {
+            let ranking = rank(&boards, &nums, &pattern);
+            assert_eq!(play(&boards, &nums, &pattern), ranking.wins);
+        }
+    }
+
+    #[test]
+    fn repeated_numbers() {
+        // calling 1 marks both copies and finishes the top row
+        let (nums, boards) = parse("1,2,3\n\n1 1\n2 3\n").unwrap();
+        let pattern = WinPattern::default();
+        let wins = play(&boards, &nums, &pattern);
+        assert_eq!((0, 5), (wins[0].turn, wins[0].score));
+        assert_eq!(wins, rank(&boards, &nums, &pattern).wins);
+
+        let mut board = boards[0].clone();
+        assert_eq!(Some((vec![0, 1], vec![0])), board.mark_cells(1));
+        assert_eq!(None, board.mark_cells(1));
+    }
+
+    #[test]
+    fn simultaneous_winners() {
+        let input = indoc! {"
+            1,2,3,4,10,20
+
+            1 2 3
+            4 5 6
+            7 8 9
+
+            3 2 1
+            10 11 12
+            13 14 15
+
+            20 21 22
+            23 24 25
+            26 27 28
+        "};
+        let (nums, boards) = parse(input).unwrap();
+        let ranking = rank(&boards, &nums, &WinPattern::default());
+
+        // boards 0 and 1 both complete their top row on the 3
+        let rounds = ranking.by_turn();
+        assert_eq!(1, rounds.len());
+        assert_eq!(
+            vec![(0, 117), (1, 225)],
+            rounds[0]
+                .iter()
+                .map(|w| (w.board, w.score))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![2], ranking.never);
+
+        let pattern = WinPattern::default();
+        let first = |tie| first_winner(&boards, &nums, &pattern, tie).map(|w| w.board);
+        assert_eq!(Some(0), first(TiePolicy::BoardOrder));
+        assert_eq!(Some(1), first(TiePolicy::HighestScore));
+        assert_eq!(Some(0), first(TiePolicy::LowestScore));
+
+        // with the only round tied, the last winner is picked the same way
+        let last = |tie| last_winner(&boards, &nums, &pattern, tie).map(|w| w.board);
+        assert_eq!(Some(1), last(TiePolicy::HighestScore));
+        assert_eq!(Some(0), last(TiePolicy::LowestScore));
+
+        // equal scores fall back to board order
+        let twins = [boards[0].clone(), boards[0].clone()];
+        let first = first_winner(&twins, &nums, &pattern, TiePolicy::HighestScore);
+        assert_eq!(Some(0), first.map(|w| w.board));
     }
 }
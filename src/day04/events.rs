@@ -0,0 +1,372 @@
+// Bingo game events, for logging a game as JSON Lines or replaying it in a terminal.
+
+use super::{Board, LineKind, WinPattern};
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    NumberCalled {
+        turn: usize,
+        number: usize,
+    },
+    CellMarked {
+        board: usize,
+        row: usize,
+        col: usize,
+    },
+    LineCompleted {
+        board: usize,
+        line: LineKind,
+    },
+    BoardWon {
+        board: usize,
+        turn: usize,
+        number: usize,
+        score: usize,
+    },
+}
+
+impl Event {
+    pub fn to_json(&self) -> String {
+        match self {
+            Event::NumberCalled { turn, number } => {
+                format!(
+                    r#"{{"event":"NumberCalled","turn":{},"number":{}}}"#,
+                    turn, number
+                )
+            }
+            Event::CellMarked { board, row, col } => format!(
+                r#"{{"event":"CellMarked","board":{},"row":{},"col":{}}}"#,
+                board, row, col
+            ),
+            Event::LineCompleted { board, line } => format!(
+                r#"{{"event":"LineCompleted","board":{},"line":"{}"}}"#,
+                board, line
+            ),
+            Event::BoardWon { board, turn, number, score } => format!(
+                r#"{{"event":"BoardWon","board":{},"turn":{},"number":{},"score":{}}}"#,
+                board, turn, number, score
+            ),
+        }
+    }
+}
+
+// Play the game like play() does, recording everything that happens. Boards
+// stop being marked once they've won, and the game stops when they all have.
+pub fn events(boards: &[Board], nums: &[usize], pattern: &WinPattern) -> Vec<Event> {
+    let mut boards = boards.to_vec();
+    for board in boards.iter_mut() {
+        board.set_pattern(pattern);
+    }
+    let mut won = vec![false; boards.len()];
+    let mut events = vec![];
+
+    for (turn, &number) in nums.iter().enumerate() {
+        if won.iter().all(|&w| w) {
+            break;
+        }
+        events.push(Event::NumberCalled { turn, number });
+
+        for (i, board) in boards.iter_mut().enumerate() {
+            if won[i] {
+                continue;
+            }
+            let (cells, completed) = match board.mark_cells(number) {
+                Some(x) => x,
+                None => continue,
+            };
+            for cell in cells {
+                let (row, col) = (cell / board.cols, cell % board.cols);
+                events.push(Event::CellMarked { board: i, row, col });
+            }
+
+            for &line in &completed {
+                let line = board.lines[line].kind.clone();
+                events.push(Event::LineCompleted { board: i, line });
+            }
+            if !completed.is_empty() {
+                won[i] = true;
+                let score = board.score(number);
+                events.push(Event::BoardWon { board: i, turn, number, score });
+            }
+        }
+    }
+
+    events
+}
+
+pub fn write_json_lines<W: Write>(events: &[Event], mut out: W) -> io::Result<()> {
+    for event in events {
+        writeln!(out, "{}", event.to_json())?;
+    }
+    Ok(())
+}
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const INVERSE: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+// How fast to step through the calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    // wait this long after each call
+    Delay(Duration),
+    // wait for Enter after each call
+    Step,
+}
+
+// The boards as the replay has seen them so far
+struct Table<'a> {
+    boards: &'a [Board],
+    marked: Vec<Vec<bool>>,
+    // score of each board that has won
+    won: Vec<Option<usize>>,
+}
+
+impl<'a> Table<'a> {
+    fn new(boards: &'a [Board]) -> Self {
+        let marked = boards
+            .iter()
+            .map(|b| vec![false; b.numbers.len()])
+            .collect();
+        Table {
+            boards,
+            marked,
+            won: vec![None; boards.len()],
+        }
+    }
+
+    fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::CellMarked { board, row, col } => {
+                let cols = self.boards[board].cols;
+                self.marked[board][row * cols + col] = true;
+            }
+            Event::BoardWon { board, score, .. } => self.won[board] = Some(score),
+            _ => {}
+        }
+    }
+
+    // All the boards, side by side in groups that fit in `width` columns.
+    // Marked cells are shown in inverse video.
+    fn render(&self, width: usize) -> String {
+        // each board is 3 chars per column, plus a 3 char gutter
+        let board_width = |i: usize| self.boards[i].cols * 3 + 3;
+
+        let mut out = String::new();
+        let mut start = 0;
+        while start < self.boards.len() {
+            let mut end = start + 1;
+            let mut used = board_width(start);
+            while end < self.boards.len() && used + board_width(end) <= width {
+                used += board_width(end);
+                end += 1;
+            }
+            out += &self.render_group(start, end);
+            out += "\n";
+            start = end;
+        }
+        out
+    }
+
+    fn render_group(&self, start: usize, end: usize) -> String {
+        let rows = (start..end).map(|i| self.boards[i].rows).max().unwrap_or(0);
+
+        let mut title = String::new();
+        for i in start..end {
+            let name = match self.won[i] {
+                Some(_) => format!("#{} WON", i),
+                None => format!("#{}", i),
+            };
+            title += &format!("{:<w$}", name, w = self.boards[i].cols * 3 + 3);
+        }
+        let mut out = title.trim_end().to_string() + "\n";
+
+        for r in 0..rows {
+            let mut line = String::new();
+            for i in start..end {
+                let board = &self.boards[i];
+                for c in 0..board.cols {
+                    if r >= board.rows {
+                        line += "   ";
+                        continue;
+                    }
+                    let cell = r * board.cols + c;
+                    let n = format!("{:>3}", board.numbers[cell]);
+                    if self.marked[i][cell] {
+                        line += &format!("{}{}{}", INVERSE, n, RESET);
+                    } else {
+                        line += &n;
+                    }
+                }
+                line += "   ";
+            }
+            out += line.trim_end();
+            out += "\n";
+        }
+        out
+    }
+}
+
+// Replay the events in a terminal, one call at a time. Whenever a board wins
+// and pause_on_win is set, waits for Enter (read from `input`).
+pub fn replay<R: BufRead, W: Write>(
+    boards: &[Board],
+    events: &[Event],
+    speed: Speed,
+    pause_on_win: bool,
+    mut input: R,
+    mut out: W,
+) -> io::Result<()> {
+    let mut table = Table::new(boards);
+    let mut wait = || -> io::Result<()> {
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        Ok(())
+    };
+
+    // one frame per call: its NumberCalled event and everything up to the next one
+    let calls = events.chunk_by(|_, b| !matches!(b, Event::NumberCalled { .. }));
+    for call in calls {
+        let mut header = String::new();
+        let mut winners = vec![];
+        for event in call {
+            table.apply(event);
+            match *event {
+                Event::NumberCalled { turn, number } => {
+                    header = format!("Call {}: {}", turn + 1, number);
+                }
+                Event::BoardWon { board, score, .. } => winners.push((board, score)),
+                _ => {}
+            }
+        }
+
+        write!(out, "{}{}\n\n{}", CLEAR, header, table.render(80))?;
+        for (board, score) in &winners {
+            writeln!(out, "Board #{} wins with a score of {}!", board, score)?;
+        }
+
+        if pause_on_win && !winners.is_empty() {
+            write!(out, "[paused, press Enter]")?;
+            out.flush()?;
+            wait()?;
+        } else {
+            out.flush()?;
+            match speed {
+                Speed::Delay(delay) => thread::sleep(delay),
+                Speed::Step => wait()?,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, play, EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn example_events() {
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let pattern = WinPattern::default();
+        let events = events(&boards, &nums, &pattern);
+
+        assert_eq!(Event::NumberCalled { turn: 0, number: 7 }, events[0]);
+        // 7 is on every board
+        assert_eq!(Event::CellMarked { board: 0, row: 2, col: 4 }, events[1]);
+        assert_eq!(Event::CellMarked { board: 1, row: 2, col: 2 }, events[2]);
+        assert_eq!(Event::CellMarked { board: 2, row: 4, col: 4 }, events[3]);
+
+        // the same wins as play(), each right after its line
+        let wins: Vec<(usize, usize)> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::BoardWon { board, score, .. } => Some((*board, *score)),
+                _ => None,
+            })
+            .collect();
+        let expected: Vec<(usize, usize)> = play(&boards, &nums, &pattern)
+            .iter()
+            .map(|w| (w.board, w.score))
+            .collect();
+        assert_eq!(expected, wins);
+
+        let first_win = events
+            .iter()
+            .position(|e| matches!(e, Event::BoardWon { .. }))
+            .unwrap();
+        assert_eq!(
+            Event::LineCompleted { board: 2, line: LineKind::Row(0) },
+            events[first_win - 1]
+        );
+
+        // game stops with the last winner
+        assert_eq!(
+            Event::BoardWon {
+                board: 1,
+                turn: 14,
+                number: 13,
+                score: 1924
+            },
+            *events.last().unwrap()
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let events = vec![
+            Event::NumberCalled { turn: 0, number: 7 },
+            Event::CellMarked { board: 1, row: 2, col: 3 },
+            Event::LineCompleted { board: 1, line: LineKind::Column(3) },
+            Event::BoardWon {
+                board: 1,
+                turn: 0,
+                number: 7,
+                score: 42,
+            },
+        ];
+        let mut out = vec![];
+        write_json_lines(&events, &mut out).unwrap();
+        let expected = indoc::indoc! {r#"
+            {"event":"NumberCalled","turn":0,"number":7}
+            {"event":"CellMarked","board":1,"row":2,"col":3}
+            {"event":"LineCompleted","board":1,"line":"column 3"}
+            {"event":"BoardWon","board":1,"turn":0,"number":7,"score":42}
+        "#};
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn terminal_replay() {
+        let (nums, boards) = parse("1,5,9,2\n\n1 2\n3 4\n\n5 6\n7 8\n\n9 10\n11 12\n").unwrap();
+        let events = events(&boards, &nums, &WinPattern::default());
+
+        // no delay, and one Enter for the pause when board 0 wins
+        let mut out = vec![];
+        let speed = Speed::Delay(Duration::ZERO);
+        replay(&boards, &events, speed, true, "\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(4, out.matches(CLEAR).count());
+        assert!(out.contains("Call 4: 2"));
+        assert!(out.contains("Board #0 wins with a score of 14!"));
+        assert_eq!(1, out.matches("[paused, press Enter]").count());
+
+        // the last frame has all three boards side by side
+        let last = out.rsplit(CLEAR).next().unwrap();
+        let lines: Vec<&str> = last.lines().collect();
+        assert_eq!("#0 WON   #1       #2", lines[2]);
+        let marked = |n: &str| format!("{}{}{}", INVERSE, n, RESET);
+        let row = format!(
+            "{}{}   {}  6   {} 10",
+            marked("  1"),
+            marked("  2"),
+            marked("  5"),
+            marked("  9")
+        );
+        assert_eq!(row, lines[3]);
+    }
+}
@@ -0,0 +1,284 @@
+// Seeded generator for bingo inputs in the format day04::parse() reads, for
+// load tests and fuzzing.
+
+use super::rng::Rng;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    pub seed: u64,
+    pub boards: usize,
+    pub rows: usize,
+    pub cols: usize,
+    // numbers are drawn from min..=max
+    pub min: usize,
+    pub max: usize,
+    // B-I-N-G-O style: split the range into one band per column, and fill
+    // each column from its own band
+    pub classic: bool,
+}
+
+impl Default for GeneratorConfig {
+    // The same shape as the puzzle input
+    fn default() -> Self {
+        GeneratorConfig {
+            seed: 0,
+            boards: 100,
+            rows: 5,
+            cols: 5,
+            min: 0,
+            max: 99,
+            classic: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GenerateError {
+    EmptyBoard,
+    // not enough distinct numbers to fill a board (or a classic column)
+    RangeTooSmall {
+        needed: usize,
+        available: usize,
+    },
+    // every number in the range goes on the draw line, so it can't be huge
+    RangeTooLarge {
+        min: usize,
+        max: usize,
+        limit: usize,
+    },
+}
+
+// Most numbers on the draw line, about 8 MB of input
+const MAX_DRAWS: usize = 1 << 20;
+
+// Pick `count` distinct numbers from min..=max, in random order, without
+// building the range (Floyd's algorithm). Ranges can be much bigger than a board.
+fn sample(rng: &mut Rng, min: usize, max: usize, count: usize) -> Vec<usize> {
+    let n = max - min + 1;
+    let mut seen = HashSet::with_capacity(count);
+    let mut picks = Vec::with_capacity(count);
+    for j in n - count..n {
+        let t = rng.below(j + 1);
+        let pick = if seen.insert(t) { t } else { j };
+        seen.insert(pick);
+        picks.push(min + pick);
+    }
+    // Floyd's picks are a uniform set, but not in a uniform order
+    rng.shuffle(&mut picks);
+    picks
+}
+
+fn board(rng: &mut Rng, config: &GeneratorConfig) -> Vec<usize> {
+    let (rows, cols) = (config.rows, config.cols);
+    if !config.classic {
+        return sample(rng, config.min, config.max, rows * cols);
+    }
+
+    // column c gets the c-th band of the range, the last band takes the leftovers
+    let band = (config.max - config.min + 1) / cols;
+    let columns: Vec<Vec<usize>> = (0..cols)
+        .map(|c| {
+            let lo = config.min + c * band;
+            let hi = if c == cols - 1 {
+                config.max
+            } else {
+                lo + band - 1
+            };
+            sample(rng, lo, hi, rows)
+        })
+        .collect();
+    (0..rows * cols)
+        .map(|i| columns[i % cols][i / cols])
+        .collect()
+}
+
+// The whole input file: the draw order, then the boards. Every number in the
+// range is drawn, so every board fills up and at least one must win.
+pub fn generate(config: &GeneratorConfig) -> Result<String, GenerateError> {
+    let (rows, cols) = (config.rows, config.cols);
+    if rows == 0 || cols == 0 {
+        return Err(GenerateError::EmptyBoard);
+    }
+    if config.max >= config.min && config.max - config.min >= MAX_DRAWS {
+        return Err(GenerateError::RangeTooLarge {
+            min: config.min,
+            max: config.max,
+            limit: MAX_DRAWS,
+        });
+    }
+    let available = if config.max < config.min {
+        0
+    } else {
+        config.max - config.min + 1
+    };
+    let (needed, per) = if config.classic {
+        (rows, available / cols)
+    } else {
+        (rows.saturating_mul(cols), available)
+    };
+    if per < needed {
+        return Err(GenerateError::RangeTooSmall { needed, available: per });
+    }
+
+    let mut rng = Rng::new(config.seed);
+    let mut draws: Vec<usize> = (config.min..=config.max).collect();
+    rng.shuffle(&mut draws);
+
+    let mut out = draws
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    out += "\n";
+
+    let width = config.max.to_string().len();
+    for _ in 0..config.boards {
+        let numbers = board(&mut rng, config);
+        out += "\n";
+        for row in numbers.chunks(cols) {
+            let row: Vec<String> = row
+                .iter()
+                .map(|n| format!("{:>w$}", n, w = width))
+                .collect();
+            out += &row.join(" ");
+            out += "\n";
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{first_winner, parse, TiePolicy, WinPattern};
+    use super::*;
+
+    #[test]
+    fn puzzle_shaped() {
+        let config = GeneratorConfig {
+            seed: 2021,
+            ..GeneratorConfig::default()
+        };
+        let input = generate(&config).unwrap();
+        assert_eq!(input, generate(&config).unwrap());
+        assert_ne!(
+            input,
+            generate(&GeneratorConfig { seed: 2022, ..config.clone() }).unwrap()
+        );
+
+        let (nums, boards) = parse(&input).unwrap();
+        assert_eq!(100, nums.len());
+        assert_eq!(100, boards.len());
+        for board in &boards {
+            assert_eq!((5, 5), (board.rows, board.cols));
+            let mut numbers = board.numbers.clone();
+            numbers.sort();
+            numbers.dedup();
+            assert_eq!(25, numbers.len());
+            assert!(numbers.iter().all(|&n| n <= 99));
+        }
+        let winner = first_winner(
+            &boards,
+            &nums,
+            &WinPattern::default(),
+            TiePolicy::BoardOrder,
+        );
+        assert!(winner.is_some());
+    }
+
+    #[test]
+    fn classic_columns() {
+        let config = GeneratorConfig {
+            seed: 1,
+            boards: 3,
+            min: 1,
+            max: 75,
+            classic: true,
+            ..GeneratorConfig::default()
+        };
+        let (nums, boards) = parse(&generate(&config).unwrap()).unwrap();
+        assert_eq!(75, nums.len());
+        for board in &boards {
+            for (i, &n) in board.numbers.iter().enumerate() {
+                // B 1-15, I 16-30, N 31-45, G 46-60, O 61-75
+                let col = i % 5;
+                assert!(
+                    (col * 15 + 1..=col * 15 + 15).contains(&n),
+                    "{} in column {}",
+                    n,
+                    col
+                );
+            }
+        }
+
+        let narrow = GeneratorConfig { max: 20, ..config.clone() };
+        let err = GenerateError::RangeTooSmall { needed: 5, available: 4 };
+        assert_eq!(Err(err), generate(&narrow));
+        let small = GeneratorConfig {
+            rows: 3,
+            cols: 4,
+            min: 0,
+            max: 10,
+            classic: false,
+            ..config
+        };
+        let err = GenerateError::RangeTooSmall { needed: 12, available: 11 };
+        assert_eq!(Err(err), generate(&small));
+
+        let huge = GeneratorConfig {
+            min: 5,
+            max: usize::MAX,
+            ..small.clone()
+        };
+        let err = GenerateError::RangeTooLarge {
+            min: 5,
+            max: usize::MAX,
+            limit: MAX_DRAWS,
+        };
+        assert_eq!(Err(err), generate(&huge));
+        let backwards = GeneratorConfig { min: 10, max: 0, ..small.clone() };
+        let err = GenerateError::RangeTooSmall { needed: 12, available: 0 };
+        assert_eq!(Err(err), generate(&backwards));
+        let wide = GeneratorConfig { rows: usize::MAX, cols: 2, ..small };
+        let err = GenerateError::RangeTooSmall { needed: usize::MAX, available: 11 };
+        assert_eq!(Err(err), generate(&wide));
+    }
+
+    #[test]
+    fn sample_wide_range() {
+        // would be a terabyte as a Vec
+        let mut rng = Rng::new(5);
+        let mut picks = sample(&mut rng, 10, 10 + (1 << 40), 25);
+        assert!(picks.iter().all(|&n| (10..=10 + (1 << 40)).contains(&n)));
+        picks.sort();
+        picks.dedup();
+        assert_eq!(25, picks.len());
+
+        let mut all = sample(&mut rng, 3, 9, 7);
+        all.sort();
+        assert_eq!((3..=9).collect::<Vec<_>>(), all);
+    }
+
+    #[test]
+    fn odd_sizes() {
+        let config = GeneratorConfig {
+            seed: 9,
+            boards: 4,
+            rows: 3,
+            cols: 7,
+            min: 100,
+            max: 999,
+            ..GeneratorConfig::default()
+        };
+        let input = generate(&config).unwrap();
+        assert!(input
+            .lines()
+            .nth(2)
+            .unwrap()
+            .split(' ')
+            .all(|x| x.len() == 3));
+        let (nums, boards) = parse(&input).unwrap();
+        assert_eq!(900, nums.len());
+        assert!(boards.iter().all(|b| (b.rows, b.cols) == (3, 7)));
+    }
+}
@@ -0,0 +1,265 @@
+// Monte Carlo odds: reshuffle the draw order many times to estimate how likely
+// each board is to win first or last, and on which turn it usually wins.
+//
+// Trial i always uses the same shuffle for a given seed, however the trials
+// are split across threads, so results are reproducible.
+
+use super::rng::Rng;
+use super::{rank, Board, TiePolicy, WinPattern};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub trials: usize,
+    pub seed: u64,
+    pub threads: usize,
+    // who counts as first/last when boards win on the same call
+    pub tie: TiePolicy,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation {
+            trials: 10_000,
+            seed: 0,
+            threads: 4,
+            tie: TiePolicy::BoardOrder,
+        }
+    }
+}
+
+// An estimate with a 95% confidence interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub estimate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Odds {
+    pub board: usize,
+    pub first: Interval,
+    pub last: Interval,
+    // mean turn (index into the draws) the board wins on, in trials where it wins
+    pub turn: Interval,
+    pub wins: usize,
+}
+
+const Z: f64 = 1.96;
+
+// Wilson score interval for a proportion
+fn proportion(hits: usize, n: usize) -> Interval {
+    if n == 0 {
+        return Interval { estimate: 0.0, low: 0.0, high: 1.0 };
+    }
+    let (p, n) = (hits as f64 / n as f64, n as f64);
+    let centre = (p + Z * Z / (2.0 * n)) / (1.0 + Z * Z / n);
+    let spread = Z / (1.0 + Z * Z / n) * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+    Interval {
+        estimate: p,
+        low: (centre - spread).max(0.0),
+        high: (centre + spread).min(1.0),
+    }
+}
+
+// Normal interval for a mean, from the sum and sum of squares
+fn mean(sum: f64, sum_sq: f64, n: usize) -> Interval {
+    if n == 0 {
+        return Interval {
+            estimate: f64::NAN,
+            low: f64::NAN,
+            high: f64::NAN,
+        };
+    }
+    let n = n as f64;
+    let m = sum / n;
+    let variance = if n > 1.0 {
+        ((sum_sq - sum * m) / (n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+    let spread = Z * (variance / n).sqrt();
+    Interval {
+        estimate: m,
+        low: m - spread,
+        high: m + spread,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Tally {
+    first: Vec<usize>,
+    last: Vec<usize>,
+    wins: Vec<usize>,
+    turn_sum: Vec<f64>,
+    turn_sq: Vec<f64>,
+}
+
+impl Tally {
+    fn new(boards: usize) -> Self {
+        Tally {
+            first: vec![0; boards],
+            last: vec![0; boards],
+            wins: vec![0; boards],
+            turn_sum: vec![0.0; boards],
+            turn_sq: vec![0.0; boards],
+        }
+    }
+
+    fn add(&mut self, other: &Tally) {
+        for i in 0..self.first.len() {
+            self.first[i] += other.first[i];
+            self.last[i] += other.last[i];
+            self.wins[i] += other.wins[i];
+            self.turn_sum[i] += other.turn_sum[i];
+            self.turn_sq[i] += other.turn_sq[i];
+        }
+    }
+}
+
+// Shuffle for trial i, independent of every other trial
+fn trial_rng(seed: u64, trial: usize) -> Rng {
+    let mut rng = Rng::new(seed ^ (trial as u64).wrapping_mul(0xd1b54a32d192ed03));
+    rng.fork()
+}
+
+fn run_trials(
+    boards: &[Board],
+    pool: &[usize],
+    pattern: &WinPattern,
+    sim: &Simulation,
+    trials: std::ops::Range<usize>,
+) -> Tally {
+    let mut tally = Tally::new(boards.len());
+    let mut draws = pool.to_vec();
+    for trial in trials {
+        draws.copy_from_slice(pool);
+        trial_rng(sim.seed, trial).shuffle(&mut draws);
+
+        let ranking = rank(boards, &draws, pattern);
+        if let Some(w) = ranking.first_by(sim.tie) {
+            tally.first[w.board] += 1;
+        }
+        if let Some(w) = ranking.last_by(sim.tie) {
+            tally.last[w.board] += 1;
+        }
+        for w in &ranking.wins {
+            tally.wins[w.board] += 1;
+            tally.turn_sum[w.board] += w.turn as f64;
+            tally.turn_sq[w.board] += (w.turn * w.turn) as f64;
+        }
+    }
+    tally
+}
+
+// Odds for every board, drawing every number in the pool in a random order
+pub fn odds(boards: &[Board], pool: &[usize], pattern: &WinPattern, sim: &Simulation) -> Vec<Odds> {
+    let threads = sim.threads.clamp(1, sim.trials.max(1));
+    let per_thread = sim.trials.div_ceil(threads);
+
+    let tally = thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let start = (t * per_thread).min(sim.trials);
+                let end = ((t + 1) * per_thread).min(sim.trials);
+                s.spawn(move || run_trials(boards, pool, pattern, sim, start..end))
+            })
+            .collect();
+
+        let mut total = Tally::new(boards.len());
+        for handle in handles {
+            total.add(&handle.join().unwrap());
+        }
+        total
+    });
+
+    (0..boards.len())
+        .map(|i| Odds {
+            board: i,
+            first: proportion(tally.first[i], sim.trials),
+            last: proportion(tally.last[i], sim.trials),
+            turn: mean(tally.turn_sum[i], tally.turn_sq[i], tally.wins[i]),
+            wins: tally.wins[i],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let pattern = WinPattern::default();
+        let sim = Simulation {
+            trials: 500,
+            seed: 7,
+            threads: 1,
+            ..Simulation::default()
+        };
+
+        let odds1 = odds(&boards, &nums, &pattern, &sim);
+        let odds4 = odds(
+            &boards,
+            &nums,
+            &pattern,
+            &Simulation { threads: 4, ..sim.clone() },
+        );
+        assert_eq!(odds1, odds4);
+        let other = odds(
+            &boards,
+            &nums,
+            &pattern,
+            &Simulation { seed: 8, ..sim.clone() },
+        );
+        assert_ne!(odds1, other);
+
+        // exactly one first and one last winner per trial
+        let total = |f: fn(&Odds) -> f64| odds1.iter().map(f).sum::<f64>();
+        assert!((total(|o| o.first.estimate) - 1.0).abs() < 1e-9);
+        assert!((total(|o| o.last.estimate) - 1.0).abs() < 1e-9);
+
+        for o in &odds1 {
+            // every number is drawn, so every board wins every trial
+            assert_eq!(500, o.wins);
+            for x in [&o.first, &o.last, &o.turn] {
+                assert!(x.low <= x.estimate && x.estimate <= x.high);
+            }
+            assert!(o.turn.estimate >= 4.0 && o.turn.estimate < 27.0);
+        }
+    }
+
+    #[test]
+    fn lucky_board() {
+        // each 1x2 board wins on either of its numbers (its columns are single
+        // cells), so the 1x1 board only wins first if 1 is called first
+        let (nums, boards) = parse("1,2,3,4,5\n\n1\n\n2 3\n\n4 5\n").unwrap();
+        let sim = Simulation {
+            trials: 2000,
+            seed: 1,
+            ..Simulation::default()
+        };
+        let odds = odds(&boards, &nums, &WinPattern::default(), &sim);
+
+        let first = &odds[0].first;
+        assert!(first.low < 0.2 && 0.2 < first.high, "{:?}", first);
+        // 1 is called on turn 2 on average, a pair's first number on turn 1
+        assert!((odds[0].turn.estimate - 2.0).abs() < 0.1);
+        assert!((odds[1].turn.estimate - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn intervals() {
+        let x = proportion(50, 100);
+        assert_eq!(0.5, x.estimate);
+        assert!((x.low - 0.4038).abs() < 1e-3 && (x.high - 0.5962).abs() < 1e-3);
+        assert_eq!(0.0, proportion(0, 10).low);
+
+        let m = mean(10.0, 50.0, 2);
+        assert_eq!(5.0, m.estimate);
+        assert!(mean(0.0, 0.0, 0).estimate.is_nan());
+    }
+}
@@ -0,0 +1,337 @@
+// A bingo server for local multiplayer games over TCP, with a line-based
+// protocol that's easy to drive from netcat.
+//
+// Client --> server:
+//   NAME <name>      set the name announced when your boards win
+//   BOARD            then the board's rows, finished by a blank line
+//   START            start the game with every board submitted so far
+//   QUIT             disconnect
+//
+// Server --> client:
+//   WELCOME <player>                 on connecting
+//   OK <what>                        a command worked
+//   ERROR <why>                      it didn't
+//   GAME <n> boards                  to everyone, when a game starts
+//   DRAW <call> <number>             to everyone, call counts from 1
+//   MARK <board> <row> <col>         to the board's owner
+//   LINE <board> <line>              to the board's owner
+//   WIN <board> <score> <name>       to everyone
+//   GAME OVER                        to everyone, then boards can be sent again
+//
+// The game itself is events() over the submitted boards, so marks, wins and
+// scores are exactly those of a replay.
+
+use super::events::{events, Event};
+use super::rng::Rng;
+use super::{parse_board, Board, WinPattern};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    // numbers to call, in this order unless there's a seed
+    pub draws: Vec<usize>,
+    // reshuffle the draws for every game
+    pub seed: Option<u64>,
+    // pause between calls
+    pub delay: Duration,
+    pub pattern: WinPattern,
+    // stop after this many games, or run forever
+    pub games: Option<usize>,
+    // a client that doesn't read its messages for this long is dropped, so
+    // it can't hold up everyone else's game
+    pub write_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            draws: (0..=99).collect(),
+            seed: None,
+            delay: Duration::from_secs(2),
+            pattern: WinPattern::default(),
+            games: None,
+            write_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+enum Message {
+    Joined(usize, TcpStream),
+    Line(usize, String),
+    Left(usize),
+}
+
+struct Player {
+    out: TcpStream,
+    name: String,
+    // rows of a board being sent, after BOARD
+    pending: Option<Vec<String>>,
+}
+
+struct Lobby {
+    players: BTreeMap<usize, Player>,
+    // (board, owner)
+    boards: Vec<(Board, usize)>,
+    playing: bool,
+    start: Option<usize>,
+    // boards the pattern can't win on are refused
+    pattern: WinPattern,
+    write_timeout: Duration,
+}
+
+impl Lobby {
+    // A player whose write fails or times out is dropped. Shutting the
+    // socket down ends their reader thread too.
+    fn send(&mut self, player: usize, msg: &str) {
+        if let Some(p) = self.players.get_mut(&player) {
+            if writeln!(p.out, "{}", msg).is_err() {
+                self.drop_player(player);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, msg: &str) {
+        let failed: Vec<usize> = self
+            .players
+            .iter_mut()
+            .filter_map(|(&id, p)| writeln!(p.out, "{}", msg).is_err().then_some(id))
+            .collect();
+        for id in failed {
+            self.drop_player(id);
+        }
+    }
+
+    fn drop_player(&mut self, id: usize) {
+        if let Some(p) = self.players.remove(&id) {
+            let _ = p.out.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn handle(&mut self, msg: Message) {
+        match msg {
+            Message::Joined(id, out) => {
+                if out.set_write_timeout(Some(self.write_timeout)).is_err() {
+                    return;
+                }
+                let name = format!("player{}", id);
+                let player = Player { out, name, pending: None };
+                self.players.insert(id, player);
+                self.send(id, &format!("WELCOME {}", id));
+            }
+            Message::Left(id) => {
+                self.players.remove(&id);
+            }
+            Message::Line(id, line) => self.command(id, line.trim_end()),
+        }
+    }
+
+    fn command(&mut self, id: usize, line: &str) {
+        let Some(player) = self.players.get_mut(&id) else {
+            return;
+        };
+        if let Some(rows) = &mut player.pending {
+            if !line.trim().is_empty() {
+                rows.push(line.to_string());
+                return;
+            }
+            let rows = player.pending.take().unwrap();
+            return self.submit(id, &rows.join("\n"));
+        }
+
+        let (cmd, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match cmd.to_ascii_uppercase().as_str() {
+            "" => {}
+            "NAME" if arg.trim().is_empty() => self.send(id, "ERROR NAME needs a name"),
+            "NAME" => {
+                player.name = arg.trim().to_string();
+                let msg = format!("OK name {}", player.name);
+                self.send(id, &msg);
+            }
+            "BOARD" if self.playing => self.send(id, "ERROR game in progress"),
+            "BOARD" => player.pending = Some(vec![]),
+            "START" if self.playing => self.send(id, "ERROR game in progress"),
+            "START" if self.boards.is_empty() => self.send(id, "ERROR no boards yet"),
+            "START" => self.start = Some(id),
+            "QUIT" => {
+                let _ = player.out.shutdown(Shutdown::Both);
+                self.players.remove(&id);
+            }
+            _ => self.send(id, &format!("ERROR unknown command {}", cmd)),
+        }
+    }
+
+    fn submit(&mut self, id: usize, text: &str) {
+        if self.playing {
+            return self.send(id, "ERROR game in progress");
+        }
+        let board = parse_board(text).and_then(|board| {
+            self.pattern.check(std::slice::from_ref(&board))?;
+            Ok(board)
+        });
+        match board {
+            Ok(board) => {
+                self.boards.push((board, id));
+                let msg = format!("OK board {}", self.boards.len() - 1);
+                self.send(id, &msg);
+            }
+            Err(e) => self.send(id, &format!("ERROR {:?}", e)),
+        }
+    }
+
+    // Tell each player what happened to their boards, and everyone the calls and wins
+    fn announce(&mut self, event: &Event) {
+        match event {
+            Event::NumberCalled { turn, number } => {
+                self.broadcast(&format!("DRAW {} {}", turn + 1, number));
+            }
+            Event::CellMarked { board, row, col } => {
+                let owner = self.boards[*board].1;
+                self.send(owner, &format!("MARK {} {} {}", board, row, col));
+            }
+            Event::LineCompleted { board, line } => {
+                let owner = self.boards[*board].1;
+                self.send(owner, &format!("LINE {} {}", board, line));
+            }
+            Event::BoardWon { board, score, .. } => {
+                let owner = self.boards[*board].1;
+                let name = match self.players.get(&owner) {
+                    Some(p) => p.name.clone(),
+                    None => format!("player{}", owner),
+                };
+                self.broadcast(&format!("WIN {} {} {}", board, score, name));
+            }
+        }
+    }
+}
+
+// Accept connections forever, each with a thread reading its lines
+fn accept(listener: TcpListener, tx: Sender<Message>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else { continue };
+        let Ok(out) = stream.try_clone() else {
+            continue;
+        };
+        if tx.send(Message::Joined(id, out)).is_err() {
+            return;
+        }
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(Message::Line(id, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(Message::Left(id));
+        });
+    }
+}
+
+// Handle messages until `deadline`, or until a game is started if there's none
+fn wait(lobby: &mut Lobby, rx: &Receiver<Message>, deadline: Option<Instant>) -> bool {
+    loop {
+        let msg = match deadline {
+            Some(d) => match rx.recv_timeout(d.saturating_duration_since(Instant::now())) {
+                Ok(msg) => msg,
+                Err(RecvTimeoutError::Timeout) => return true,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            },
+            None => match rx.recv() {
+                Ok(msg) => msg,
+                Err(_) => return false,
+            },
+        };
+        lobby.handle(msg);
+        if deadline.is_none() && lobby.start.is_some() {
+            return true;
+        }
+    }
+}
+
+// Serve games on the listener, one after another
+pub fn serve(listener: TcpListener, config: ServerConfig) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || accept(listener, tx));
+
+    let mut rng = config.seed.map(Rng::new);
+    let mut lobby = Lobby {
+        players: BTreeMap::new(),
+        boards: vec![],
+        playing: false,
+        start: None,
+        pattern: config.pattern.clone(),
+        write_timeout: config.write_timeout,
+    };
+
+    let mut games = 0;
+    while config.games.is_none_or(|n| games < n) {
+        if !wait(&mut lobby, &rx, None) {
+            return Ok(());
+        }
+        let mut draws = config.draws.clone();
+        if let Some(rng) = &mut rng {
+            rng.fork().shuffle(&mut draws);
+        }
+        let boards: Vec<Board> = lobby.boards.iter().map(|(b, _)| b.clone()).collect();
+        let events = events(&boards, &draws, &config.pattern);
+
+        lobby.playing = true;
+        let starter = lobby.start.take().unwrap();
+        lobby.send(starter, "OK start");
+        lobby.broadcast(&format!("GAME {} boards", boards.len()));
+
+        let calls = events.chunk_by(|_, b| !matches!(b, Event::NumberCalled { .. }));
+        for (i, call) in calls.enumerate() {
+            if i > 0 && !wait(&mut lobby, &rx, Some(Instant::now() + config.delay)) {
+                return Ok(());
+            }
+            for event in call {
+                lobby.announce(event);
+            }
+        }
+
+        lobby.broadcast("GAME OVER");
+        lobby.boards.clear();
+        lobby.playing = false;
+        games += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_writes_drop_the_player() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let mut lobby = Lobby {
+            players: BTreeMap::new(),
+            boards: vec![],
+            playing: false,
+            start: None,
+            pattern: WinPattern::default(),
+            write_timeout: Duration::from_millis(100),
+        };
+        lobby.handle(Message::Joined(0, stream));
+        assert!(lobby.players.contains_key(&0));
+
+        // once the client has gone, a write fails and the player goes too
+        drop(client);
+        for _ in 0..100 {
+            lobby.broadcast("DRAW 1 7");
+            if lobby.players.is_empty() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("player still there after their connection closed");
+    }
+}
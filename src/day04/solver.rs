@@ -0,0 +1,363 @@
+// Adversarial draw orders: choose which numbers to call, and in what order, to
+// make a particular board win first, win last, or never win.
+//
+// Every order found is checked by replaying it with play() before it's
+// returned. When there's no such order, the answer says why, and an order the
+// replay disagrees with comes back as Solution::ReplayFailed rather than a panic.
+
+use super::{play, Board, LineKind, WinPattern};
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq)]
+pub enum Solution {
+    // numbers to call, in order
+    Order(Vec<usize>),
+    Impossible(Proof),
+    // the order found didn't do what it should when replayed: a solver bug,
+    // not a proof that there's no order
+    ReplayFailed(Vec<usize>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Proof {
+    // none of the board's lines can be completed from the pool of numbers
+    NoWinningLine,
+    // every line of the board also completes a line of another board, at the
+    // latest on the same call: (line, other board, other board's line)
+    EveryLineBeaten(Vec<(LineKind, usize, LineKind)>),
+    // tried every way of keeping one number of each of the board's lines out
+    // of the draw, and some other board always needed one of them
+    Exhausted { nodes: usize },
+}
+
+// The order if its replay did what it should
+fn checked(order: Vec<usize>, replayed: bool) -> Solution {
+    if replayed {
+        Solution::Order(order)
+    } else {
+        Solution::ReplayFailed(order)
+    }
+}
+
+// A board's winning lines as sets of numbers, leaving out lines that need a
+// number outside the pool
+struct Lines {
+    kinds: Vec<LineKind>,
+    numbers: Vec<HashSet<usize>>,
+}
+
+fn lines(board: &Board, pattern: &WinPattern, pool: &HashSet<usize>) -> Lines {
+    let mut kinds = vec![];
+    let mut numbers = vec![];
+    for line in pattern.lines(board.rows, board.cols) {
+        let ns: HashSet<usize> = line.cells.iter().map(|&c| board.numbers[c]).collect();
+        if ns.is_subset(pool) {
+            kinds.push(line.kind);
+            numbers.push(ns);
+        }
+    }
+    Lines { kinds, numbers }
+}
+
+// Numbers of a line in board order, so orders come out the same every time
+fn in_board_order(board: &Board, numbers: &HashSet<usize>) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    board
+        .numbers
+        .iter()
+        .copied()
+        .filter(|n| numbers.contains(n) && seen.insert(*n))
+        .collect()
+}
+
+// The shortest order that makes board k win first, alone on its winning call.
+//
+// Any such order has to call every number of one of k's lines, so the best is
+// k's shortest line that doesn't also complete a line of another board.
+pub fn shortest_first_win(
+    boards: &[Board],
+    k: usize,
+    pool: &[usize],
+    pattern: &WinPattern,
+) -> Solution {
+    let pool: HashSet<usize> = pool.iter().copied().collect();
+    let mine = lines(&boards[k], pattern, &pool);
+    if mine.numbers.is_empty() {
+        return Solution::Impossible(Proof::NoWinningLine);
+    }
+    let others: Vec<(usize, Lines)> = (0..boards.len())
+        .filter(|&j| j != k)
+        .map(|j| (j, lines(&boards[j], pattern, &pool)))
+        .collect();
+
+    let mut beaten = vec![];
+    let mut best: Option<&HashSet<usize>> = None;
+    for (kind, numbers) in mine.kinds.iter().zip(&mine.numbers) {
+        let beater = others.iter().find_map(|(j, theirs)| {
+            let i = theirs.numbers.iter().position(|ns| ns.is_subset(numbers))?;
+            Some((*j, theirs.kinds[i].clone()))
+        });
+        match beater {
+            Some((j, theirs)) => beaten.push((kind.clone(), j, theirs)),
+            None if best.is_none_or(|b| numbers.len() < b.len()) => best = Some(numbers),
+            None => {}
+        }
+    }
+
+    match best {
+        Some(numbers) => {
+            let order = in_board_order(&boards[k], numbers);
+            let replayed = wins_first(boards, k, &order, pattern);
+            checked(order, replayed)
+        }
+        None => Solution::Impossible(Proof::EveryLineBeaten(beaten)),
+    }
+}
+
+// An order where every other board that can win does, and then board k wins.
+pub fn last_win(boards: &[Board], k: usize, pool: &[usize], pattern: &WinPattern) -> Solution {
+    let pool_set: HashSet<usize> = pool.iter().copied().collect();
+    let mine = lines(&boards[k], pattern, &pool_set);
+    if mine.numbers.is_empty() {
+        return Solution::Impossible(Proof::NoWinningLine);
+    }
+    let mut order = match others_win(boards, k, pool, pattern) {
+        Ok(order) => order,
+        Err(proof) => return Solution::Impossible(proof),
+    };
+
+    // then finish k's shortest line
+    let line = mine.numbers.iter().min_by_key(|ns| ns.len()).unwrap();
+    let called: HashSet<usize> = order.iter().copied().collect();
+    order.extend(
+        in_board_order(&boards[k], line)
+            .into_iter()
+            .filter(|n| !called.contains(n)),
+    );
+
+    let replayed = wins_last(boards, k, &order, pattern);
+    checked(order, replayed)
+}
+
+// An order where every other board that can win does, and board k never wins.
+pub fn never_wins(boards: &[Board], k: usize, pool: &[usize], pattern: &WinPattern) -> Solution {
+    match others_win(boards, k, pool, pattern) {
+        Ok(order) => {
+            let replayed = play(boards, &order, pattern).iter().all(|w| w.board != k);
+            checked(order, replayed)
+        }
+        Err(proof) => Solution::Impossible(proof),
+    }
+}
+
+// Calls that let every other board (that can) win while k can't, if there are any.
+//
+// k can't win as long as one number of each of its lines is never called.
+// Search over those "blockers", one per line, for a set that leaves every
+// other board a line without any of them. Calling those lines is the order.
+fn others_win(
+    boards: &[Board],
+    k: usize,
+    pool: &[usize],
+    pattern: &WinPattern,
+) -> Result<Vec<usize>, Proof> {
+    let pool: HashSet<usize> = pool.iter().copied().collect();
+    let mine = lines(&boards[k], pattern, &pool);
+    let others: Vec<(usize, Lines)> = (0..boards.len())
+        .filter(|&j| j != k)
+        .map(|j| (j, lines(&boards[j], pattern, &pool)))
+        .filter(|(_, theirs)| !theirs.numbers.is_empty())
+        .collect();
+
+    let mut search = Search {
+        mine: &mine.numbers,
+        others: &others,
+        blockers: HashSet::new(),
+        nodes: 0,
+    };
+    if !search.run(0) {
+        return Err(Proof::Exhausted { nodes: search.nodes });
+    }
+
+    let mut order = vec![];
+    let mut called = HashSet::new();
+    for (j, theirs) in &others {
+        let line = theirs
+            .numbers
+            .iter()
+            .find(|ns| ns.is_disjoint(&search.blockers))
+            .unwrap();
+        for n in in_board_order(&boards[*j], line) {
+            if called.insert(n) {
+                order.push(n);
+            }
+        }
+    }
+    Ok(order)
+}
+
+struct Search<'a> {
+    mine: &'a [HashSet<usize>],
+    others: &'a [(usize, Lines)],
+    blockers: HashSet<usize>,
+    nodes: usize,
+}
+
+impl Search<'_> {
+    // Pick blockers for lines i.. of k, keeping every other board winnable
+    fn run(&mut self, i: usize) -> bool {
+        self.nodes += 1;
+        if i == self.mine.len() {
+            return true;
+        }
+        let line = &self.mine[i];
+        if !line.is_disjoint(&self.blockers) {
+            // already blocked by an earlier choice
+            return self.run(i + 1);
+        }
+
+        let mut candidates: Vec<usize> = line.iter().copied().collect();
+        candidates.sort_unstable();
+        for n in candidates {
+            self.blockers.insert(n);
+            let winnable = self.others.iter().all(|(_, theirs)| {
+                theirs
+                    .numbers
+                    .iter()
+                    .any(|ns| ns.is_disjoint(&self.blockers))
+            });
+            if winnable && self.run(i + 1) {
+                return true;
+            }
+            self.blockers.remove(&n);
+        }
+        false
+    }
+}
+
+// Does board k win on its own, before anyone else?
+fn wins_first(boards: &[Board], k: usize, order: &[usize], pattern: &WinPattern) -> bool {
+    let wins = play(boards, order, pattern);
+    match &wins[..] {
+        [first, rest @ ..] => first.board == k && rest.first().is_none_or(|w| w.turn > first.turn),
+        [] => false,
+    }
+}
+
+// Does board k win on its own, after everyone else who wins?
+fn wins_last(boards: &[Board], k: usize, order: &[usize], pattern: &WinPattern) -> bool {
+    let wins = play(boards, order, pattern);
+    match &wins[..] {
+        [rest @ .., last] => last.board == k && rest.last().is_none_or(|w| w.turn < last.turn),
+        [] => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, Board, EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn example_orders() {
+        let (nums, boards) = parse(EXAMPLE).unwrap();
+        let pattern = WinPattern::default();
+
+        for k in 0..boards.len() {
+            match shortest_first_win(&boards, k, &nums, &pattern) {
+                Solution::Order(order) => {
+                    assert_eq!(5, order.len());
+                    assert!(wins_first(&boards, k, &order, &pattern));
+                }
+                x => panic!("no first win for {}: {:?}", k, x),
+            }
+            match last_win(&boards, k, &nums, &pattern) {
+                Solution::Order(order) => assert!(wins_last(&boards, k, &order, &pattern)),
+                x => panic!("no last win for {}: {:?}", k, x),
+            }
+            assert!(matches!(
+                never_wins(&boards, k, &nums, &pattern),
+                Solution::Order(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn puzzle_input() {
+        let (nums, boards) = super::super::load_input("input/input04.txt");
+        let pattern = WinPattern::default();
+        for k in [0, 42, 99] {
+            assert!(matches!(
+                shortest_first_win(&boards, k, &nums, &pattern),
+                Solution::Order(_)
+            ));
+            assert!(matches!(
+                last_win(&boards, k, &nums, &pattern),
+                Solution::Order(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn impossible() {
+        let pattern = WinPattern::default();
+        let a = Board::new(2, 2, vec![1, 2, 3, 4]);
+        let transposed = Board::new(2, 2, vec![1, 3, 2, 4]);
+        let pool = [1, 2, 3, 4, 5];
+
+        // every line of a is a line of its transpose, so they always tie
+        let boards = [a.clone(), transposed];
+        match shortest_first_win(&boards, 0, &pool, &pattern) {
+            Solution::Impossible(Proof::EveryLineBeaten(beaten)) => {
+                assert_eq!((LineKind::Row(0), 1, LineKind::Column(0)), beaten[0]);
+                assert_eq!(4, beaten.len());
+            }
+            x => panic!("{:?}", x),
+        }
+        // and neither can let the other finish without finishing too
+        assert!(matches!(
+            last_win(&boards, 0, &pool, &pattern),
+            Solution::Impossible(Proof::Exhausted { .. })
+        ));
+        assert!(matches!(
+            never_wins(&boards, 1, &pool, &pattern),
+            Solution::Impossible(_)
+        ));
+
+        // 4 is never called
+        let boards = [a.clone(), Board::new(1, 2, vec![5, 6])];
+        let no_four = [1, 2, 3, 5, 6];
+        assert_eq!(
+            Solution::Order(vec![1, 2]),
+            shortest_first_win(&boards, 0, &no_four, &pattern)
+        );
+        let small = Board::new(1, 1, vec![9]);
+        assert_eq!(
+            Solution::Impossible(Proof::NoWinningLine),
+            shortest_first_win(&[small.clone(), a.clone()], 0, &no_four, &pattern)
+        );
+
+        // a can't win first against a board that only needs the 1, but can win last
+        let boards = [a, small, Board::new(1, 1, vec![1])];
+        let pool = [1, 2, 3, 4, 9];
+        assert_eq!(
+            Solution::Order(vec![3, 4]),
+            shortest_first_win(&boards, 0, &pool, &pattern)
+        );
+        assert_eq!(
+            Solution::Order(vec![9, 1, 2]),
+            last_win(&boards, 0, &pool, &pattern)
+        );
+        assert_eq!(
+            Solution::Order(vec![9, 1]),
+            never_wins(&boards, 0, &pool, &pattern)
+        );
+
+        // a bad order is reported, not returned
+        let replayed = wins_first(&boards, 0, &[1, 2], &pattern);
+        assert_eq!(
+            Solution::ReplayFailed(vec![1, 2]),
+            checked(vec![1, 2], replayed)
+        );
+    }
+}
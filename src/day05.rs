@@ -5,9 +5,14 @@
 
 use indoc::indoc;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::read_to_string;
 
+pub mod field;
+pub mod paths;
+pub mod render;
+pub mod zones;
+
 const EXAMPLE: &str = indoc! {"
     0,9 -> 5,9
     8,0 -> 0,8
@@ -21,92 +26,596 @@ const EXAMPLE: &str = indoc! {"
     5,5 -> 8,2
 "};
 
-#[derive(Debug)]
-struct Segment {
-    x1: i32,
-    y1: i32,
-    x2: i32,
-    y2: i32,
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
-struct Point {
-    x: i32,
-    y: i32,
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    // not "x1,y1 -> x2,y2"
+    BadLine { line: usize, text: String },
+    // a coordinate that doesn't fit in an i32
+    BadNumber { line: usize, text: String },
 }
 
-fn to_int(x: regex::Match) -> i32 {
-    x.as_str().parse().unwrap()
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ParseOptions {
+    // drop segments that start and end at the same point
+    pub drop_degenerate: bool,
+    // drop segments covering the same points as an earlier one
+    pub drop_duplicates: bool,
 }
 
-fn parse(input: &str) -> Vec<Segment> {
-    let line_format = Regex::new(r"(\d+),(\d+) -> (\d+),(\d+)").expect("Invalid regex");
+// Why a segment was dropped
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    Degenerate { line: usize },
+    // first is the line of the segment it duplicates
+    Duplicate { line: usize, first: usize },
+}
+
+fn to_int(x: &str, line: usize) -> Result<i32, ParseError> {
+    x.parse()
+        .map_err(|_| ParseError::BadNumber { line, text: x.to_string() })
+}
+
+// Every line as a segment, like the puzzle
+pub fn parse(input: &str) -> Result<Vec<Segment>, ParseError> {
+    parse_with(input, ParseOptions::default()).map(|(segments, _)| segments)
+}
+
+// String --> segments, and warnings for any that were dropped.
+// Coordinates can be signed, spaces around the numbers and the arrow are
+// optional, and blank lines are skipped. Line numbers count from 1.
+pub fn parse_with(
+    input: &str,
+    options: ParseOptions,
+) -> Result<(Vec<Segment>, Vec<Warning>), ParseError> {
+    let line_format =
+        Regex::new(r"^\s*([+-]?\d+)\s*,\s*([+-]?\d+)\s*->\s*([+-]?\d+)\s*,\s*([+-]?\d+)\s*$")
+            .expect("Invalid regex");
+
+    let mut segments = vec![];
+    let mut warnings = vec![];
+    // segment, ends in order --> line first seen on
+    let mut seen: HashMap<Segment, usize> = HashMap::new();
+    for (i, text) in input.lines().enumerate() {
+        let line = i + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let caps = line_format
+            .captures(text)
+            .ok_or_else(|| ParseError::BadLine { line, text: text.to_string() })?;
+        let n = |i: usize| to_int(&caps[i], line);
+        let s = Segment {
+            x1: n(1)?,
+            y1: n(2)?,
+            x2: n(3)?,
+            y2: n(4)?,
+        };
 
-    input
-        .lines()
-        .map(|x| {
-            let points = line_format.captures(x).unwrap();
-            Segment {
-                x1: points.get(1).map_or(0, to_int),
-                y1: points.get(2).map_or(0, to_int),
-                x2: points.get(3).map_or(0, to_int),
-                y2: points.get(4).map_or(0, to_int),
+        if options.drop_degenerate && (s.x1, s.y1) == (s.x2, s.y2) {
+            warnings.push(Warning::Degenerate { line });
+            continue;
+        }
+        if options.drop_duplicates {
+            let key = if (s.x1, s.y1) <= (s.x2, s.y2) {
+                s.clone()
+            } else {
+                Segment {
+                    x1: s.x2,
+                    y1: s.y2,
+                    x2: s.x1,
+                    y2: s.y1,
+                }
+            };
+            if let Some(&first) = seen.get(&key) {
+                warnings.push(Warning::Duplicate { line, first });
+                continue;
             }
-        })
-        .collect()
+            seen.insert(key, line);
+        }
+        segments.push(s);
+    }
+    Ok((segments, warnings))
 }
 
 fn load_input(filename: &str) -> Vec<Segment> {
-    let input = read_to_string(&filename).unwrap();
-    parse(&input)
+    let input = read_to_string(filename).unwrap();
+    parse(&input).unwrap()
 }
 
 fn horizontal_or_vertical(s: &Segment) -> bool {
     s.x1 == s.x2 || s.y1 == s.y2
 }
 
-fn plot_points(segments: Vec<Segment>) -> HashMap<Point, i32> {
-    let mut points = HashMap::new();
+// How a segment is turned into grid points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Raster {
+    // horizontal, vertical or 45° only, like the puzzle. Anything else is an error.
+    Straight,
+    // the points exactly on the segment, for any slope
+    Lattice,
+    // one point per step along the longer axis, the closest to the segment
+    Bresenham,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotError {
+    // segment is an index into the input
+    UnsupportedSlope { segment: usize, dx: i64, dy: i64 },
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Every point lies between the ends, so it fits back in an i32
+fn point(x: i64, y: i64) -> Point {
+    Point { x: x as i32, y: y as i32 }
+}
+
+fn lattice_points(s: &Segment) -> Vec<Point> {
+    let (dx, dy) = delta(s);
+    // a single point has gcd 0
+    let steps = gcd(dx, dy);
+    let (sx, sy) = if steps == 0 {
+        (0, 0)
+    } else {
+        (dx / steps, dy / steps)
+    };
+    let (x1, y1) = (s.x1 as i64, s.y1 as i64);
+    (0..=steps)
+        .map(|i| point(x1 + i * sx, y1 + i * sy))
+        .collect()
+}
+
+fn bresenham_points(s: &Segment) -> Vec<Point> {
+    let (dx, dy) = delta(s);
+    let (sx, sy) = (dx.signum(), dy.signum());
+    let (dx, dy) = (dx.abs(), -dy.abs());
+
+    let mut points = vec![];
+    let (mut x, mut y) = (s.x1 as i64, s.y1 as i64);
+    let end = (s.x2 as i64, s.y2 as i64);
+    let mut err = dx + dy;
+    loop {
+        points.push(point(x, y));
+        if (x, y) == end {
+            return points;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+// (dx, dy), which can overflow an i32
+fn delta(s: &Segment) -> (i64, i64) {
+    (s.x2 as i64 - s.x1 as i64, s.y2 as i64 - s.y1 as i64)
+}
+
+fn is_straight(s: &Segment) -> bool {
+    let (dx, dy) = delta(s);
+    dx == 0 || dy == 0 || dx.abs() == dy.abs()
+}
+
+// Reject the first segment that isn't horizontal, vertical or 45°
+fn check_straight(segments: &[Segment]) -> Result<(), PlotError> {
+    match segments.iter().position(|s| !is_straight(s)) {
+        Some(i) => {
+            let (dx, dy) = delta(&segments[i]);
+            Err(PlotError::UnsupportedSlope { segment: i, dx, dy })
+        }
+        None => Ok(()),
+    }
+}
+
+fn segment_points(s: &Segment, raster: Raster) -> Vec<Point> {
+    match raster {
+        // for straight segments the lattice points are every step
+        Raster::Straight | Raster::Lattice => lattice_points(s),
+        Raster::Bresenham => bresenham_points(s),
+    }
+}
+
+// Overlap counts, either in a Vec covering the bounding box or in a HashMap
+// of just the visited points
+#[derive(Debug, Clone, PartialEq)]
+pub enum Grid {
+    Dense(Dense),
+    Sparse(HashMap<Point, i32>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dense {
+    min_x: i32,
+    min_y: i32,
+    width: usize,
+    height: usize,
+    counts: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Auto,
+    Dense,
+    Sparse,
+}
+
+// Dense is worth it when the segments cover at least 1 cell in this many...
+const DENSITY: u64 = 16;
+// ...and the box isn't too big to allocate anyway (4 bytes a cell)
+const MAX_DENSE_CELLS: u64 = 1 << 24;
+
+// (min x, min y, max x, max y) of the segments' ends. Every rasterized point
+// is inside it.
+fn bounding_box(segments: &[Segment]) -> Option<(i32, i32, i32, i32)> {
+    segments.iter().fold(None, |bbox, s| {
+        let (x0, x1) = (s.x1.min(s.x2), s.x1.max(s.x2));
+        let (y0, y1) = (s.y1.min(s.y2), s.y1.max(s.y2));
+        Some(match bbox {
+            None => (x0, y0, x1, y1),
+            Some((a, b, c, d)) => (a.min(x0), b.min(y0), c.max(x1), d.max(y1)),
+        })
+    })
+}
+
+impl Grid {
+    fn new(segments: &[Segment], backend: Backend) -> Self {
+        let Some((x0, y0, x1, y1)) = bounding_box(segments) else {
+            return Grid::Sparse(HashMap::new());
+        };
+        let width = (x1 as i64 - x0 as i64 + 1) as u64;
+        let height = (y1 as i64 - y0 as i64 + 1) as u64;
+        // None when the box spans too much of the i32 range to count cells
+        let area = width
+            .checked_mul(height)
+            .filter(|&a| usize::try_from(a).is_ok());
+
+        let dense = match backend {
+            // still sparse if the box couldn't be allocated at all
+            Backend::Dense => area.is_some(),
+            Backend::Sparse => false,
+            Backend::Auto => {
+                // the most cells the segments can touch
+                let cells: u64 = segments
+                    .iter()
+                    .map(|s| {
+                        let (dx, dy) = delta(s);
+                        dx.unsigned_abs().max(dy.unsigned_abs()) + 1
+                    })
+                    .sum();
+                area.is_some_and(|a| a <= MAX_DENSE_CELLS && a <= cells.saturating_mul(DENSITY))
+            }
+        };
+        let Some(area) = area.filter(|_| dense) else {
+            return Grid::Sparse(HashMap::new());
+        };
+        Grid::Dense(Dense {
+            min_x: x0,
+            min_y: y0,
+            width: width as usize,
+            height: height as usize,
+            counts: vec![0; area as usize],
+        })
+    }
+
+    fn add(&mut self, p: Point) {
+        match self {
+            Grid::Dense(d) => {
+                let i = d.index(p).expect("point outside the grid");
+                d.counts[i] += 1;
+            }
+            Grid::Sparse(m) => *m.entry(p).or_insert(0) += 1,
+        }
+    }
+
+    pub fn get(&self, p: Point) -> i32 {
+        match self {
+            Grid::Dense(d) => d.index(p).map_or(0, |i| d.counts[i]),
+            Grid::Sparse(m) => m.get(&p).copied().unwrap_or(0),
+        }
+    }
+
+    // Every point with a vent, and how many
+    pub fn points(&self) -> Box<dyn Iterator<Item = (Point, i32)> + '_> {
+        match self {
+            Grid::Dense(d) => Box::new(
+                d.counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c > 0)
+                    .map(|(i, &c)| (d.point(i), c)),
+            ),
+            Grid::Sparse(m) => Box::new(m.iter().map(|(&p, &c)| (p, c))),
+        }
+    }
+
+    // Number of points with at least `min` vents
+    pub fn overlaps(&self, min: i32) -> usize {
+        self.points().filter(|&(_, c)| c >= min).count()
+    }
+
+    // Most vents at any one point
+    pub fn max(&self) -> i32 {
+        self.points().map(|(_, c)| c).max().unwrap_or(0)
+    }
+}
+
+// Counts for arbitrary points, kept sparse
+impl FromIterator<(Point, i32)> for Grid {
+    fn from_iter<I: IntoIterator<Item = (Point, i32)>>(iter: I) -> Self {
+        Grid::Sparse(iter.into_iter().collect())
+    }
+}
+
+impl Dense {
+    fn index(&self, p: Point) -> Option<usize> {
+        let x = usize::try_from(p.x as i64 - self.min_x as i64).ok()?;
+        let y = usize::try_from(p.y as i64 - self.min_y as i64).ok()?;
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    fn point(&self, i: usize) -> Point {
+        Point {
+            x: self.min_x + (i % self.width) as i32,
+            y: self.min_y + (i / self.width) as i32,
+        }
+    }
+}
+
+// Every segment's slope is checked before anything is plotted
+fn rasterize(segments: &[Segment], raster: Raster) -> Result<Grid, PlotError> {
+    rasterize_with(segments, raster, Backend::Auto)
+}
+
+fn rasterize_with(
+    segments: &[Segment],
+    raster: Raster,
+    backend: Backend,
+) -> Result<Grid, PlotError> {
+    if raster == Raster::Straight {
+        check_straight(segments)?;
+    }
+
+    let mut grid = Grid::new(segments, backend);
+    for s in segments {
+        for p in segment_points(s, raster) {
+            grid.add(p);
+        }
+    }
+    Ok(grid)
+}
+
+pub fn plot_points(segments: Vec<Segment>) -> Result<Grid, PlotError> {
+    rasterize(&segments, Raster::Straight)
+}
+
+// Counting overlaps from the geometry, for coordinates too big to rasterize.
+//
+// Each straight segment is an interval on one line. Points covered twice on
+// the same line come from merging that line's intervals; the rest are where
+// two different lines cross, and there are few enough of those to list.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Direction {
+    // y = key
+    Horizontal,
+    // x = key
+    Vertical,
+    // x - y = key
+    Diagonal,
+    // x + y = key
+    AntiDiagonal,
+}
+
+impl Direction {
+    // (a, b) of the line a*x + b*y = key
+    fn normal(self) -> (i64, i64) {
+        match self {
+            Direction::Horizontal => (0, 1),
+            Direction::Vertical => (1, 0),
+            Direction::Diagonal => (1, -1),
+            Direction::AntiDiagonal => (1, 1),
+        }
+    }
+
+    // Position along the line: y for vertical lines, x otherwise
+    fn param(self, x: i64, y: i64) -> i64 {
+        match self {
+            Direction::Vertical => y,
+            _ => x,
+        }
+    }
+
+    fn point(self, key: i64, t: i64) -> (i64, i64) {
+        match self {
+            Direction::Horizontal => (t, key),
+            Direction::Vertical => (key, t),
+            Direction::Diagonal => (t, t - key),
+            Direction::AntiDiagonal => (t, key - t),
+        }
+    }
+}
+
+// The line through a straight segment and its interval (inclusive) on it.
+// A single point is taken to be horizontal.
+fn line_of(s: &Segment) -> (Direction, i64, i64, i64) {
+    let (x1, y1, x2, y2) = (s.x1 as i64, s.y1 as i64, s.x2 as i64, s.y2 as i64);
+    let dir = if y1 == y2 {
+        Direction::Horizontal
+    } else if x1 == x2 {
+        Direction::Vertical
+    } else if (x2 - x1).signum() == (y2 - y1).signum() {
+        Direction::Diagonal
+    } else {
+        Direction::AntiDiagonal
+    };
+    let (a, b) = dir.normal();
+    let (t1, t2) = (dir.param(x1, y1), dir.param(x2, y2));
+    (dir, a * x1 + b * y1, t1.min(t2), t1.max(t2))
+}
+
+// Inclusive intervals along a line
+type Spans = Vec<(i64, i64)>;
 
+// Merge intervals on one line: (covered at least once, covered at least twice)
+fn merge(mut intervals: Spans) -> (Spans, Spans) {
+    // +1 at the start, -1 just past the end
+    let mut edges: Vec<(i64, i32)> = intervals
+        .drain(..)
+        .flat_map(|(from, to)| [(from, 1), (to + 1, -1)])
+        .collect();
+    edges.sort_unstable();
+
+    let (mut once, mut twice) = (vec![], vec![]);
+    let push = |spans: &mut Spans, from: i64, to: i64| match spans.last_mut() {
+        Some(last) if last.1 + 1 == from => last.1 = to,
+        _ => spans.push((from, to)),
+    };
+    let mut depth = 0;
+    for (i, &(t, change)) in edges.iter().enumerate() {
+        depth += change;
+        // the depth holds until the next edge
+        let Some(&(next, _)) = edges.get(i + 1) else {
+            break;
+        };
+        if next > t {
+            if depth >= 1 {
+                push(&mut once, t, next - 1);
+            }
+            if depth >= 2 {
+                push(&mut twice, t, next - 1);
+            }
+        }
+    }
+    (once, twice)
+}
+
+fn contains(spans: &[(i64, i64)], t: i64) -> bool {
+    let i = spans.partition_point(|&(_, to)| to < t);
+    spans.get(i).is_some_and(|&(from, _)| from <= t)
+}
+
+// Number of points covered by two or more segments, without visiting them
+fn count_overlaps(segments: &[Segment]) -> Result<u64, PlotError> {
+    check_straight(segments)?;
+
+    let mut lines: HashMap<(Direction, i64), Spans> = HashMap::new();
     for s in segments {
-        let dx = (s.x2 - s.x1).signum();
-        let dy = (s.y2 - s.y1).signum();
+        let (dir, key, from, to) = line_of(s);
+        lines.entry((dir, key)).or_default().push((from, to));
+    }
 
-        // iterate x1,y1 --> x2,y2. The +dx is critical to include the endpoint.
-        let mut x = s.x1;
-        let mut y = s.y1;
-        while (x, y) != (s.x2 + dx, s.y2 + dy) {
-            let count = points.entry(Point { x, y }).or_insert(0);
-            *count += 1;
-            x += dx;
-            y += dy;
+    // every line's merged pieces, and the points already covered twice on it
+    let mut pieces = vec![];
+    let mut twice: HashMap<(Direction, i64), Spans> = HashMap::new();
+    let mut count: u64 = 0;
+    for ((dir, key), intervals) in lines {
+        let (once, more) = merge(intervals);
+        count += more
+            .iter()
+            .map(|&(from, to)| (to - from + 1) as u64)
+            .sum::<u64>();
+        pieces.extend(once.into_iter().map(|(from, to)| (dir, key, from, to)));
+        if !more.is_empty() {
+            twice.insert((dir, key), more);
         }
     }
 
-    return points;
+    // then where pieces of different lines cross
+    let mut crossings = HashSet::new();
+    for (i, &(d1, k1, from1, to1)) in pieces.iter().enumerate() {
+        for &(d2, k2, from2, to2) in &pieces[i + 1..] {
+            if d1 == d2 {
+                // parallel, or the same line, which is merged already
+                continue;
+            }
+            let ((a1, b1), (a2, b2)) = (d1.normal(), d2.normal());
+            let det = a1 * b2 - a2 * b1;
+            let (nx, ny) = (k1 * b2 - k2 * b1, a1 * k2 - a2 * k1);
+            if nx % det != 0 || ny % det != 0 {
+                // the lines cross between grid points
+                continue;
+            }
+            let (x, y) = (nx / det, ny / det);
+            let (t1, t2) = (d1.param(x, y), d2.param(x, y));
+            if (from1..=to1).contains(&t1) && (from2..=to2).contains(&t2) {
+                crossings.insert((x, y));
+            }
+        }
+    }
+    let directions = [
+        Direction::Horizontal,
+        Direction::Vertical,
+        Direction::Diagonal,
+        Direction::AntiDiagonal,
+    ];
+    // A crossing is one more point, unless it's covered twice on some line
+    // already. If it's covered twice on more than one, it was counted again
+    // for each of them.
+    for (x, y) in crossings {
+        let counted = directions
+            .iter()
+            .filter(|&&dir| {
+                let (a, b) = dir.normal();
+                twice
+                    .get(&(dir, a * x + b * y))
+                    .is_some_and(|spans| contains(spans, dir.param(x, y)))
+            })
+            .count() as u64;
+        match counted {
+            0 => count += 1,
+            n => count -= n - 1,
+        }
+    }
+    Ok(count)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn example_1() {
-        let ls = parse(EXAMPLE);
+        let ls = parse(EXAMPLE).unwrap();
         assert_eq!(10, ls.len());
 
         let lines = ls.into_iter().filter(horizontal_or_vertical).collect();
-        let points = plot_points(lines);
-        let count = points.into_values().filter(|&x| x > 1).count();
+        let points = plot_points(lines).unwrap();
+        let count = points.overlaps(2);
 
         assert_eq!(5, count);
     }
 
     #[test]
     fn example_2() {
-        let lines = parse(EXAMPLE);
-        let points = plot_points(lines);
-        let count = points.into_values().filter(|&x| x > 1).count();
+        let lines = parse(EXAMPLE).unwrap();
+        let points = plot_points(lines).unwrap();
+        let count = points.overlaps(2);
 
         assert_eq!(12, count);
     }
@@ -116,8 +625,8 @@ mod tests {
         let ls = load_input("input/input05.txt");
 
         let lines = ls.into_iter().filter(horizontal_or_vertical).collect();
-        let points = plot_points(lines);
-        let count = points.into_values().filter(|&x| x > 1).count();
+        let points = plot_points(lines).unwrap();
+        let count = points.overlaps(2);
 
         assert_eq!(6267, count);
     }
@@ -125,9 +634,211 @@ mod tests {
     #[test]
     fn part_2() {
         let lines = load_input("input/input05.txt");
-        let points = plot_points(lines);
-        let count = points.into_values().filter(|&x| x > 1).count();
+        let points = plot_points(lines).unwrap();
+        let count = points.overlaps(2);
 
         assert_eq!(20196, count);
     }
+
+    // Shorthand for the tests here and in the submodules
+    pub(super) fn p(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    pub(super) fn seg(x1: i32, y1: i32, x2: i32, y2: i32) -> Segment {
+        Segment { x1, y1, x2, y2 }
+    }
+
+    fn counts(grid: &Grid) -> HashMap<Point, i32> {
+        grid.points().collect()
+    }
+
+    #[test]
+    fn any_slope() {
+        let steep = vec![seg(0, 0, 2, 6), seg(0, 3, 2, 3)];
+        let err = PlotError::UnsupportedSlope { segment: 0, dx: 2, dy: 6 };
+        assert_eq!(Err(err), plot_points(steep.clone()));
+
+        // (0,0) (1,3) (2,6), crossing the other segment at (1,3)
+        let lattice = rasterize(&steep, Raster::Lattice).unwrap();
+        assert_eq!(5, lattice.points().count());
+        assert_eq!(2, lattice.get(Point { x: 1, y: 3 }));
+
+        // one point per row, still through (1,3)
+        let cover = rasterize(&steep, Raster::Bresenham).unwrap();
+        let on_steep = bresenham_points(&steep[0]);
+        assert_eq!(7, on_steep.len());
+        assert_eq!(Point { x: 2, y: 6 }, on_steep[6]);
+        assert_eq!(2, cover.get(Point { x: 1, y: 3 }));
+
+        assert_eq!(vec![Point { x: 4, y: 4 }], lattice_points(&seg(4, 4, 4, 4)));
+        assert_eq!(1, bresenham_points(&seg(4, 4, 4, 4)).len());
+
+        // ends 4 billion apart, which overflows an i32 difference
+        let far = seg(-2_000_000_000, 0, 2_000_000_000, 2);
+        assert_eq!(
+            vec![
+                Point { x: -2_000_000_000, y: 0 },
+                Point { x: 0, y: 1 },
+                Point { x: 2_000_000_000, y: 2 },
+            ],
+            lattice_points(&far)
+        );
+        let wide = [far, seg(-2_000_000_000, 2, 2_000_000_000, 0)];
+        let grid = rasterize(&wide, Raster::Lattice).unwrap();
+        assert!(matches!(grid, Grid::Sparse(_)));
+        assert_eq!(1, grid.overlaps(2));
+        assert_eq!(2, grid.get(Point { x: 0, y: 1 }));
+        let near = seg(i32::MIN, i32::MAX, i32::MIN + 3, i32::MAX - 6);
+        assert_eq!(
+            Point { x: i32::MIN + 3, y: i32::MAX - 6 },
+            bresenham_points(&near)[6]
+        );
+    }
+
+    #[test]
+    fn backends() {
+        let segments = load_input("input/input05.txt");
+        let dense = rasterize_with(&segments, Raster::Straight, Backend::Dense).unwrap();
+        let sparse = rasterize_with(&segments, Raster::Straight, Backend::Sparse).unwrap();
+        assert!(matches!(dense, Grid::Dense(_)));
+        assert!(matches!(sparse, Grid::Sparse(_)));
+        assert_eq!(20196, dense.overlaps(2));
+        assert_eq!(20196, sparse.overlaps(2));
+        assert_eq!(counts(&sparse), counts(&dense));
+
+        // the puzzle's segments fill a good part of their box
+        let auto = rasterize(&segments, Raster::Straight).unwrap();
+        assert!(matches!(auto, Grid::Dense(_)));
+
+        // two short segments a million apart don't
+        let far = vec![
+            seg(0, 0, 3, 0),
+            seg(1_000_000, -1_000_000, 1_000_000, -999_990),
+        ];
+        let auto = rasterize(&far, Raster::Straight).unwrap();
+        assert!(matches!(auto, Grid::Sparse(_)));
+        assert_eq!(15, auto.overlaps(1));
+        assert_eq!(1, auto.get(Point { x: 1_000_000, y: -999_995 }));
+        assert_eq!(0, auto.get(Point { x: 4, y: 0 }));
+
+        // a box over the whole i32 range has more cells than a u64 can count
+        let corners = vec![
+            seg(i32::MIN, i32::MIN, i32::MIN, i32::MIN),
+            seg(i32::MAX, i32::MAX, i32::MAX, i32::MAX),
+        ];
+        for backend in [Backend::Auto, Backend::Dense] {
+            let grid = rasterize_with(&corners, Raster::Straight, backend).unwrap();
+            assert!(matches!(grid, Grid::Sparse(_)));
+            assert_eq!(2, grid.overlaps(1));
+        }
+        assert_eq!(0, plot_points(corners).unwrap().overlaps(2));
+    }
+
+    #[test]
+    fn analytic() {
+        assert_eq!(Ok(12), count_overlaps(&parse(EXAMPLE).unwrap()));
+        let segments = load_input("input/input05.txt");
+        let rasterized = rasterize(&segments, Raster::Straight).unwrap();
+        assert_eq!(Ok(rasterized.overlaps(2) as u64), count_overlaps(&segments));
+        let straight: Vec<Segment> = segments
+            .into_iter()
+            .filter(horizontal_or_vertical)
+            .collect();
+        assert_eq!(Ok(6267), count_overlaps(&straight));
+
+        // 2 billion and 1 points shared on y = 0, the crossing at (-5, 0), and the
+        // diagonals crossing both verticals
+        let huge = vec![
+            seg(-2_000_000_000, 0, 2_000_000_000, 0),
+            seg(2_000_000_000, 0, 0, 0),
+            seg(5, -1_000_000_000, 5, 1_000_000_000),
+            seg(-5, -1_000_000_000, -5, 1_000_000_000),
+            // x + y = 1 crosses x - y = 0 between grid points
+            seg(-1_000, 1_001, 1_000, -999),
+            seg(-1_000, -1_000, 1_000, 1_000),
+        ];
+        assert_eq!(Ok(2_000_000_001 + 1 + 4), count_overlaps(&huge));
+
+        let err = PlotError::UnsupportedSlope { segment: 1, dx: 1, dy: 2 };
+        assert_eq!(
+            Err(err),
+            count_overlaps(&[seg(0, 0, 1, 1), seg(0, 0, 1, 2)])
+        );
+    }
+
+    #[test]
+    fn lenient_parsing() {
+        let input = "-1,-2 -> 3,+4\n\n  10 ,20->30, 40  \n0,0 -> 0,0\n3,4 -> -1,-2\n";
+        let segments = parse(input).unwrap();
+        assert_eq!(4, segments.len());
+        assert_eq!(seg(-1, -2, 3, 4), segments[0]);
+        assert_eq!(seg(10, 20, 30, 40), segments[1]);
+
+        let options = ParseOptions {
+            drop_degenerate: true,
+            drop_duplicates: true,
+        };
+        let (segments, warnings) = parse_with(input, options).unwrap();
+        assert_eq!(vec![seg(-1, -2, 3, 4), seg(10, 20, 30, 40)], segments);
+        let expected = vec![
+            Warning::Degenerate { line: 4 },
+            Warning::Duplicate { line: 5, first: 1 },
+        ];
+        assert_eq!(expected, warnings);
+
+        let bad = |line: usize, text: &str| ParseError::BadLine { line, text: text.to_string() };
+        assert_eq!(Err(bad(2, "1,2 -> 3")), parse("0,0 -> 1,1\n1,2 -> 3"));
+        assert_eq!(Err(bad(1, "1,2 => 3,4")), parse("1,2 => 3,4"));
+        assert_eq!(Err(bad(1, "1,- 2 -> 3,4")), parse("1,- 2 -> 3,4"));
+        let too_big = ParseError::BadNumber {
+            line: 1,
+            text: "9999999999".to_string(),
+        };
+        assert_eq!(Err(too_big), parse("9999999999,0 -> 0,0"));
+    }
+
+    proptest! {
+        #[test]
+        fn rasters_agree(x1 in -50..50, y1 in -50..50, x2 in -50..50, y2 in -50..50) {
+            let s = seg(x1, y1, x2, y2);
+            let lattice = lattice_points(&s);
+            let cover = bresenham_points(&s);
+
+            prop_assert_eq!(Point { x: x1, y: y1 }, cover[0]);
+            prop_assert_eq!(Point { x: x2, y: y2 }, *cover.last().unwrap());
+            let steps = (x2 - x1).abs().max((y2 - y1).abs());
+            prop_assert_eq!(steps as usize + 1, cover.len());
+            // exact points are always the closest ones
+            prop_assert!(lattice.iter().all(|p| cover.contains(p)));
+            if is_straight(&s) {
+                prop_assert_eq!(lattice, cover);
+            }
+        }
+
+        #[test]
+        fn same_counts(ends in prop::collection::vec((-20..20, -20..20, -20..20, -20..20), 0..10)) {
+            let segments: Vec<Segment> = ends.into_iter().map(|(a, b, c, d)| seg(a, b, c, d)).collect();
+            let dense = rasterize_with(&segments, Raster::Bresenham, Backend::Dense).unwrap();
+            let sparse = rasterize_with(&segments, Raster::Bresenham, Backend::Sparse).unwrap();
+            prop_assert_eq!(sparse.overlaps(2), dense.overlaps(2));
+            prop_assert_eq!(counts(&sparse), counts(&dense));
+        }
+
+        #[test]
+        fn analytic_matches_raster(
+            lines in prop::collection::vec((-6..6, -6..6, 0..4, 0..8), 0..30)
+        ) {
+            // (start, direction, length), so every segment is straight
+            let segments: Vec<Segment> = lines
+                .into_iter()
+                .map(|(x, y, dir, len)| {
+                    let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)][dir as usize];
+                    seg(x, y, x + dx * len, y + dy * len)
+                })
+                .collect();
+            let grid = rasterize(&segments, Raster::Straight).unwrap();
+            prop_assert_eq!(Ok(grid.overlaps(2) as u64), count_overlaps(&segments));
+        }
+    }
 }
@@ -0,0 +1,124 @@
+// A plotted vent field that can be queried without replotting, remembering
+// which segments put a vent at each point.
+
+use super::{check_straight, segment_points, PlotError, Point, Raster, Segment};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone)]
+pub struct VentField {
+    segments: Vec<Segment>,
+    // point --> ids (indexes into segments) of the segments through it
+    hits: HashMap<Point, Vec<usize>>,
+    // y --> x --> count, for rectangles
+    rows: BTreeMap<i32, BTreeMap<i32, usize>>,
+    // every point by count, highest first, then top to bottom and left to right
+    ranked: Vec<(Point, usize)>,
+}
+
+impl VentField {
+    pub fn new(segments: Vec<Segment>, raster: Raster) -> Result<Self, PlotError> {
+        if raster == Raster::Straight {
+            check_straight(&segments)?;
+        }
+
+        let mut hits: HashMap<Point, Vec<usize>> = HashMap::new();
+        for (id, s) in segments.iter().enumerate() {
+            for p in segment_points(s, raster) {
+                hits.entry(p).or_default().push(id);
+            }
+        }
+
+        let mut rows: BTreeMap<i32, BTreeMap<i32, usize>> = BTreeMap::new();
+        for (p, ids) in &hits {
+            rows.entry(p.y).or_default().insert(p.x, ids.len());
+        }
+        let mut ranked: Vec<(Point, usize)> = hits.iter().map(|(&p, ids)| (p, ids.len())).collect();
+        ranked.sort_unstable_by_key(|&(p, count)| (usize::MAX - count, p.y, p.x));
+
+        Ok(VentField { segments, hits, rows, ranked })
+    }
+
+    pub fn segment(&self, id: usize) -> &Segment {
+        &self.segments[id]
+    }
+
+    // How many vents are at p
+    pub fn count(&self, p: Point) -> usize {
+        self.hits.get(&p).map_or(0, |ids| ids.len())
+    }
+
+    // Ids of the segments through p, in input order
+    pub fn segments_at(&self, p: Point) -> &[usize] {
+        self.hits.get(&p).map_or(&[], |ids| ids)
+    }
+
+    // The point with the most vents in the rectangle between the corners
+    // (inclusive), the first in reading order if there's a tie
+    pub fn max_in(&self, min: Point, max: Point) -> Option<(Point, usize)> {
+        if min.x > max.x || min.y > max.y {
+            return None;
+        }
+        let mut best: Option<(Point, usize)> = None;
+        for (&y, row) in self.rows.range(min.y..=max.y) {
+            for (&x, &count) in row.range(min.x..=max.x) {
+                if best.is_none_or(|(_, c)| count > c) {
+                    best = Some((Point { x, y }, count));
+                }
+            }
+        }
+        best
+    }
+
+    // The k points with the most vents
+    pub fn top(&self, k: usize) -> &[(Point, usize)] {
+        &self.ranked[..k.min(self.ranked.len())]
+    }
+
+    // Number of points with at least `min` vents
+    pub fn overlaps(&self, min: usize) -> usize {
+        self.ranked.partition_point(|&(_, count)| count >= min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::p;
+    use super::super::{load_input, parse, plot_points, EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn example_queries() {
+        let field = VentField::new(parse(EXAMPLE).unwrap(), Raster::Straight).unwrap();
+
+        assert_eq!(3, field.count(p(4, 4)));
+        assert_eq!(&[1, 2, 8], field.segments_at(p(4, 4)));
+        assert_eq!(&[2, 5, 9], field.segments_at(p(6, 4)));
+        assert_eq!(&Segment { x1: 8, y1: 0, x2: 0, y2: 8 }, field.segment(1));
+        assert_eq!(0, field.count(p(1, 0)));
+        assert!(field.segments_at(p(100, 100)).is_empty());
+
+        assert_eq!(Some((p(2, 2), 2)), field.max_in(p(0, 0), p(3, 3)));
+        assert_eq!(Some((p(4, 4), 3)), field.max_in(p(0, 0), p(9, 9)));
+        assert_eq!(None, field.max_in(p(7, 5), p(9, 6)));
+        assert_eq!(None, field.max_in(p(3, 3), p(0, 0)));
+
+        assert_eq!(&[(p(4, 4), 3), (p(6, 4), 3)], field.top(2));
+        assert_eq!(12, field.overlaps(2));
+        assert_eq!(39, field.top(100).len());
+    }
+
+    #[test]
+    fn same_as_plot_points() {
+        let segments = load_input("input/input05.txt");
+        let field = VentField::new(segments.clone(), Raster::Straight).unwrap();
+        let points = plot_points(segments).unwrap();
+
+        assert_eq!(20196, field.overlaps(2));
+        for (p, count) in points.points() {
+            assert_eq!(count as usize, field.count(p));
+        }
+        let max = points.max() as usize;
+        assert_eq!(max, field.top(1)[0].1);
+        assert_eq!(max, field.max_in(p(0, 0), p(999, 999)).unwrap().1);
+    }
+}
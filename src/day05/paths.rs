@@ -0,0 +1,276 @@
+// Safe routes across the vent field: the cheapest path between two points
+// that never steps on more than a given number of vents, by A*.
+//
+// Routes stay within the box around every vent, the start and the goal, plus
+// one cell on each side so they can go round the outside. That box can be
+// huge for a few far-apart vents, so the search gives up after MAX_VISITED
+// points rather than run for ever.
+
+use super::zones::Connectivity;
+use super::{Grid, Point};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cost {
+    // every step costs 1
+    Steps,
+    // every step costs 1, plus the vents at the point it steps onto
+    Weighted,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    // from start to goal, inclusive
+    Safe { path: Vec<Point>, cost: u64 },
+    NoSafeRoute,
+    // the search visited MAX_VISITED points without settling it either way
+    GaveUp,
+}
+
+pub const MAX_VISITED: usize = 1 << 22;
+
+// Fewest steps between two points, which no route can beat
+fn distance(a: Point, b: Point, connectivity: Connectivity) -> u64 {
+    let dx = (a.x as i64 - b.x as i64).unsigned_abs();
+    let dy = (a.y as i64 - b.y as i64).unsigned_abs();
+    match connectivity {
+        Connectivity::Four => dx + dy,
+        Connectivity::Eight => dx.max(dy),
+    }
+}
+
+pub fn safe_path(
+    points: &Grid,
+    start: Point,
+    goal: Point,
+    max_overlap: i32,
+    connectivity: Connectivity,
+    cost: Cost,
+) -> Route {
+    search(
+        points,
+        start,
+        goal,
+        max_overlap,
+        connectivity,
+        cost,
+        MAX_VISITED,
+    )
+}
+
+// safe_path(), giving up once more than `max_visited` points have been reached
+fn search(
+    points: &Grid,
+    start: Point,
+    goal: Point,
+    max_overlap: i32,
+    connectivity: Connectivity,
+    cost: Cost,
+    max_visited: usize,
+) -> Route {
+    let vents = |p: &Point| points.get(*p);
+    if vents(&start) > max_overlap || vents(&goal) > max_overlap {
+        return Route::NoSafeRoute;
+    }
+
+    let (mut min, mut max) = (start, start);
+    for p in points.points().map(|(p, _)| p).chain([goal]) {
+        (min.x, min.y) = (min.x.min(p.x), min.y.min(p.y));
+        (max.x, max.y) = (max.x.max(p.x), max.y.max(p.y));
+    }
+    let (min_x, min_y) = (min.x as i64 - 1, min.y as i64 - 1);
+    let (max_x, max_y) = (max.x as i64 + 1, max.y as i64 + 1);
+    let inside = |p: &Point| {
+        (min_x..=max_x).contains(&(p.x as i64)) && (min_y..=max_y).contains(&(p.y as i64))
+    };
+
+    // (estimated total, cost so far, y, x), smallest first
+    let mut queue = BinaryHeap::from([Reverse((
+        distance(start, goal, connectivity),
+        0,
+        start.y,
+        start.x,
+    ))]);
+    let mut best: HashMap<Point, u64> = HashMap::from([(start, 0)]);
+    let mut came_from: HashMap<Point, Point> = HashMap::new();
+
+    while let Some(Reverse((_, so_far, y, x))) = queue.pop() {
+        let p = Point { x, y };
+        if p == goal {
+            let mut path = vec![goal];
+            while let Some(&prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev);
+            }
+            path.reverse();
+            return Route::Safe { path, cost: so_far };
+        }
+        if best.get(&p).is_some_and(|&c| c < so_far) {
+            // a cheaper way here was found after this one was queued
+            continue;
+        }
+        if best.len() > max_visited {
+            return Route::GaveUp;
+        }
+
+        for q in connectivity.neighbours(p) {
+            if !inside(&q) || vents(&q) > max_overlap {
+                continue;
+            }
+            let step = match cost {
+                Cost::Steps => 1,
+                Cost::Weighted => 1 + vents(&q) as u64,
+            };
+            let next = so_far + step;
+            if best.get(&q).is_none_or(|&c| next < c) {
+                best.insert(q, next);
+                came_from.insert(q, p);
+                queue.push(Reverse((
+                    next + distance(q, goal, connectivity),
+                    next,
+                    q.y,
+                    q.x,
+                )));
+            }
+        }
+    }
+    Route::NoSafeRoute
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{p, seg};
+    use super::super::{load_input, parse, plot_points, EXAMPLE};
+    use super::*;
+
+    // Checks the route is connected, safe, and costs what it says
+    fn check(points: &Grid, route: &Route, max_overlap: i32, cost: Cost) -> u64 {
+        let Route::Safe { path, cost: total } = route else {
+            panic!("no route");
+        };
+        let vents = |p: &Point| points.get(*p);
+        assert!(path.iter().all(|p| vents(p) <= max_overlap));
+        assert!(path
+            .windows(2)
+            .all(|w| distance(w[0], w[1], Connectivity::Eight) == 1));
+        let expected: u64 = match cost {
+            Cost::Steps => path.len() as u64 - 1,
+            Cost::Weighted => path[1..].iter().map(|p| 1 + vents(p) as u64).sum(),
+        };
+        assert_eq!(expected, *total);
+        *total
+    }
+
+    #[test]
+    fn around_a_wall() {
+        // three vents on each point of x = 2, 0 <= y <= 4
+        let wall = vec![seg(2, 0, 2, 4); 3];
+        let points = plot_points(wall).unwrap();
+        let (start, goal) = (p(0, 0), p(4, 0));
+        let four = Connectivity::Four;
+
+        // straight through if that's allowed
+        let route = safe_path(&points, start, goal, 3, four, Cost::Steps);
+        assert_eq!(4, check(&points, &route, 3, Cost::Steps));
+
+        // round the top otherwise, or when the vents cost more than the detour
+        let route = safe_path(&points, start, goal, 2, four, Cost::Steps);
+        assert_eq!(6, check(&points, &route, 2, Cost::Steps));
+        let route = safe_path(&points, start, goal, 3, four, Cost::Weighted);
+        assert_eq!(6, check(&points, &route, 3, Cost::Weighted));
+        if let Route::Safe { path, .. } = route {
+            assert!(path.iter().all(|q| points.get(*q) == 0));
+        }
+
+        // diagonal steps cut the corners
+        let route = safe_path(&points, start, goal, 2, Connectivity::Eight, Cost::Steps);
+        assert_eq!(4, check(&points, &route, 2, Cost::Steps));
+    }
+
+    #[test]
+    fn no_safe_route() {
+        let square = vec![
+            seg(0, 0, 4, 0),
+            seg(4, 0, 4, 4),
+            seg(4, 4, 0, 4),
+            seg(0, 4, 0, 0),
+        ];
+        let points = plot_points(square).unwrap();
+        let four = Connectivity::Four;
+
+        assert_eq!(
+            Route::NoSafeRoute,
+            safe_path(&points, p(6, 6), p(2, 2), 0, four, Cost::Steps)
+        );
+        let route = safe_path(&points, p(6, 6), p(2, 2), 1, four, Cost::Steps);
+        assert_eq!(8, check(&points, &route, 1, Cost::Steps));
+        // starting on a vent
+        assert_eq!(
+            Route::NoSafeRoute,
+            safe_path(&points, p(0, 0), p(2, 2), 0, four, Cost::Steps)
+        );
+        assert_eq!(
+            Route::Safe { path: vec![p(2, 2)], cost: 0 },
+            safe_path(&points, p(2, 2), p(2, 2), 0, four, Cost::Steps)
+        );
+    }
+
+    #[test]
+    fn example_field() {
+        let points = plot_points(parse(EXAMPLE).unwrap()).unwrap();
+        // the diagonals block a 4-connected route, so it goes round the edge
+        let route = safe_path(
+            &points,
+            p(0, 1),
+            p(9, 8),
+            0,
+            Connectivity::Four,
+            Cost::Steps,
+        );
+        assert!(check(&points, &route, 0, Cost::Steps) > 16);
+        let route = safe_path(
+            &points,
+            p(0, 1),
+            p(9, 8),
+            1,
+            Connectivity::Four,
+            Cost::Weighted,
+        );
+        check(&points, &route, 1, Cost::Weighted);
+    }
+
+    #[test]
+    fn far_apart() {
+        let empty = Grid::from_iter([]);
+        let eight = Connectivity::Eight;
+
+        // start and goal 4 billion apart
+        let (start, goal) = (p(-2_000_000_000, 0), p(2_000_000_000, 0));
+        assert_eq!(4_000_000_000, distance(start, goal, eight));
+        assert_eq!(
+            Route::GaveUp,
+            search(&empty, start, goal, 0, eight, Cost::Steps, 1000)
+        );
+
+        // at the edge of the i32 range there are fewer neighbours
+        let corner = p(i32::MAX, i32::MIN);
+        assert_eq!(3, eight.neighbours(corner).len());
+        let route = safe_path(
+            &empty,
+            corner,
+            p(i32::MAX - 3, i32::MIN),
+            0,
+            eight,
+            Cost::Steps,
+        );
+        assert_eq!(3, check(&empty, &route, 0, Cost::Steps));
+    }
+
+    #[test]
+    fn puzzle_input() {
+        let points = plot_points(load_input("input/input05.txt")).unwrap();
+        let (start, goal) = (p(0, 0), p(989, 989));
+        let route = safe_path(&points, start, goal, 1, Connectivity::Eight, Cost::Steps);
+        assert!(check(&points, &route, 1, Cost::Steps) >= 989);
+    }
+}
@@ -0,0 +1,201 @@
+// Pictures of the vent field: the puzzle's ASCII diagram for small fields,
+// and PGM/PPM heat maps for big ones.
+
+use super::{Grid, Point};
+use std::io::{self, Write};
+
+// Biggest pictures, in cells. The diagram is only readable for small fields,
+// but the puzzle input (1000 x 1000) still fits.
+pub const MAX_ASCII_CELLS: u64 = 1 << 20;
+pub const MAX_IMAGE_CELLS: u64 = 1 << 26;
+
+#[derive(Debug)]
+pub enum RenderError {
+    // the vents' bounding box is too big to draw
+    TooBig { width: u64, height: u64, limit: u64 },
+    Io(io::Error),
+}
+
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+type Rows<'a> = Box<dyn Iterator<Item = Vec<i32>> + 'a>;
+
+// Corners of the picture: the box around the vents, or just (0, 0) if there
+// aren't any
+fn extent(points: &Grid) -> (i32, i32, i32, i32) {
+    points
+        .points()
+        .map(|(p, _)| (p.x, p.y, p.x, p.y))
+        .reduce(|(x0, y0, x1, y1), (a, b, c, d)| (x0.min(a), y0.min(b), x1.max(c), y1.max(d)))
+        .unwrap_or((0, 0, 0, 0))
+}
+
+// (width, height) and the rows of counts, top to bottom, made as they're
+// needed. Fails if the picture would have more than `limit` cells.
+fn rows(points: &Grid, limit: u64) -> Result<((u64, u64), Rows<'_>), RenderError> {
+    let (x0, y0, x1, y1) = extent(points);
+    let width = (x1 as i64 - x0 as i64 + 1) as u64;
+    let height = (y1 as i64 - y0 as i64 + 1) as u64;
+    if width * height > limit {
+        return Err(RenderError::TooBig { width, height, limit });
+    }
+    let rows = (y0..=y1).map(move |y| (x0..=x1).map(|x| points.get(Point { x, y })).collect());
+    Ok(((width, height), Box::new(rows)))
+}
+
+// `.` for no vents, otherwise the count, or `#` from 10 up
+pub fn to_ascii(points: &Grid) -> Result<String, RenderError> {
+    let mut out = String::new();
+    let (_, rows) = rows(points, MAX_ASCII_CELLS)?;
+    for row in rows {
+        for count in row {
+            out.push(match count {
+                0 => '.',
+                1..=9 => char::from_digit(count as u32, 10).unwrap(),
+                _ => '#',
+            });
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// Black for no vents up to white for the most overlaps
+pub fn write_pgm<W: Write>(points: &Grid, mut out: W) -> Result<(), RenderError> {
+    let ((width, height), rows) = rows(points, MAX_IMAGE_CELLS)?;
+    let max = points.max().max(1);
+    write!(out, "P5\n{} {}\n255\n", width, height)?;
+    for row in rows {
+        let pixels: Vec<u8> = row.iter().map(|&c| (c * 255 / max) as u8).collect();
+        out.write_all(&pixels)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// A "hot" colour scale: black, then through red and yellow to white for the
+// most overlaps
+fn heat(count: i32, max: i32) -> [u8; 3] {
+    if count == 0 {
+        return [0, 0, 0];
+    }
+    // a single vent is dim red rather than black
+    let t = 0.25 + 0.75 * (count as f64 / max as f64);
+    let channel = |from: f64| ((t - from) * 3.0).clamp(0.0, 1.0);
+    [channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0)].map(|c| (c * 255.0).round() as u8)
+}
+
+pub fn write_ppm<W: Write>(points: &Grid, mut out: W) -> Result<(), RenderError> {
+    let ((width, height), rows) = rows(points, MAX_IMAGE_CELLS)?;
+    let max = points.max().max(1);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for row in rows {
+        let pixels: Vec<u8> = row.iter().flat_map(|&c| heat(c, max)).collect();
+        out.write_all(&pixels)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{horizontal_or_vertical, parse, plot_points, EXAMPLE};
+    use super::*;
+    use indoc::indoc;
+    use std::io::BufWriter;
+
+    #[test]
+    fn puzzle_diagrams() {
+        let segments = parse(EXAMPLE).unwrap();
+        let straight = segments
+            .iter()
+            .filter(|s| horizontal_or_vertical(s))
+            .cloned()
+            .collect();
+        let part_1 = indoc! {"
+            .......1..
+            ..1....1..
+            ..1....1..
+            .......1..
+            .112111211
+            ..........
+            ..........
+            ..........
+            ..........
+            222111....
+        "};
+        assert_eq!(part_1, to_ascii(&plot_points(straight).unwrap()).unwrap());
+
+        let part_2 = indoc! {"
+            1.1....11.
+            .111...2..
+            ..2.1.111.
+            ...1.2.2..
+            .112313211
+            ...1.2....
+            ..1...1...
+            .1.....1..
+            1.......1.
+            222111....
+        "};
+        assert_eq!(part_2, to_ascii(&plot_points(segments).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn images() {
+        let points = Grid::from_iter([(Point { x: 1, y: 0 }, 1), (Point { x: 2, y: 1 }, 4)]);
+
+        let mut pgm = vec![];
+        write_pgm(&points, &mut pgm).unwrap();
+        // cropped to the vents, x from 1 to 2
+        let header = b"P5\n2 2\n255\n";
+        assert_eq!(header, &pgm[..header.len()]);
+        assert_eq!(vec![63, 0, 0, 255], pgm[header.len()..]);
+
+        let mut ppm = vec![];
+        write_ppm(&points, &mut ppm).unwrap();
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(header, &ppm[..header.len()]);
+        let pixels = &ppm[header.len()..];
+        assert_eq!(3 * 4, pixels.len());
+        assert_eq!([0, 0, 0], pixels[3..6]);
+        assert_eq!([255, 255, 255], pixels[9..]);
+        // one vent is red, brighter than nothing but not yet yellow
+        assert_eq!([255, 80, 0], pixels[..3]);
+
+        // nothing is left sitting in a buffered writer
+        let mut buffered = BufWriter::new(vec![]);
+        write_ppm(&points, &mut buffered).unwrap();
+        assert!(buffered.buffer().is_empty());
+        assert_eq!(ppm, *buffered.get_ref());
+    }
+
+    #[test]
+    fn far_from_the_origin() {
+        // cropped to the vents, however far out they are
+        let lone = Grid::from_iter([(Point { x: 2_000_000_000, y: 2_000_000_000 }, 2)]);
+        assert_eq!("2\n", to_ascii(&lone).unwrap());
+        let mut pgm = vec![];
+        write_pgm(&lone, &mut pgm).unwrap();
+        assert_eq!(b"P5\n1 1\n255\n\xff", &pgm[..]);
+        assert_eq!(".\n", to_ascii(&Grid::from_iter([])).unwrap());
+
+        // but not two vents that far apart
+        let pair = Grid::from_iter([
+            (Point { x: -5, y: 0 }, 1),
+            (Point { x: 2_000_000_000, y: 1 }, 1),
+        ]);
+        let Err(RenderError::TooBig { width, height, limit }) = to_ascii(&pair) else {
+            panic!("drew a huge diagram");
+        };
+        assert_eq!((2_000_000_006, 2, MAX_ASCII_CELLS), (width, height, limit));
+        assert!(matches!(
+            write_ppm(&pair, vec![]),
+            Err(RenderError::TooBig { .. })
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+// Danger zones: connected groups of points with at least some number of
+// vents, found by flood fill over the counts from plot_points().
+
+use super::{Grid, Point};
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    // up, down, left, right
+    Four,
+    // and the diagonals
+    Eight,
+}
+
+impl Connectivity {
+    // Neighbours past the edge of the i32 range are left out
+    pub(super) fn neighbours(self, p: Point) -> Vec<Point> {
+        let steps: &[(i32, i32)] = match self {
+            Connectivity::Four => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        };
+        steps
+            .iter()
+            .filter_map(|&(dx, dy)| {
+                Some(Point {
+                    x: p.x.checked_add(dx)?,
+                    y: p.y.checked_add(dy)?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    // in reading order (top to bottom, left to right)
+    pub cells: Vec<Point>,
+    // corners of the bounding box, inclusive
+    pub min: Point,
+    pub max: Point,
+    // most vents at any one point
+    pub peak: i32,
+    // mean (x, y) of the cells
+    pub centroid: (f64, f64),
+}
+
+impl Zone {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn new(mut cells: Vec<Point>, points: &Grid) -> Self {
+        cells.sort_unstable_by_key(|p| (p.y, p.x));
+        let min = Point {
+            x: cells.iter().map(|p| p.x).min().unwrap(),
+            y: cells.iter().map(|p| p.y).min().unwrap(),
+        };
+        let max = Point {
+            x: cells.iter().map(|p| p.x).max().unwrap(),
+            y: cells.iter().map(|p| p.y).max().unwrap(),
+        };
+        let peak = cells.iter().map(|&p| points.get(p)).max().unwrap();
+        let n = cells.len() as f64;
+        let centroid = (
+            cells.iter().map(|p| p.x as f64).sum::<f64>() / n,
+            cells.iter().map(|p| p.y as f64).sum::<f64>() / n,
+        );
+        Zone { cells, min, max, peak, centroid }
+    }
+}
+
+// Every zone of points with at least `threshold` vents, biggest first. Zones
+// of the same size are in reading order of their first cell.
+pub fn zones(points: &Grid, threshold: i32, connectivity: Connectivity) -> Vec<Zone> {
+    let danger: HashSet<Point> = points
+        .points()
+        .filter(|&(_, count)| count >= threshold)
+        .map(|(p, _)| p)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut zones = vec![];
+    for &start in &danger {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut cells = vec![];
+        let mut queue = VecDeque::from([start]);
+        while let Some(p) = queue.pop_front() {
+            cells.push(p);
+            for q in connectivity.neighbours(p) {
+                if danger.contains(&q) && seen.insert(q) {
+                    queue.push_back(q);
+                }
+            }
+        }
+        zones.push(Zone::new(cells, points));
+    }
+
+    zones.sort_by_key(|z| (usize::MAX - z.size(), z.cells[0].y, z.cells[0].x));
+    zones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::p;
+    use super::super::{load_input, parse, plot_points, EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn example_zones() {
+        let points = plot_points(parse(EXAMPLE).unwrap()).unwrap();
+
+        let four = zones(&points, 2, Connectivity::Four);
+        let sizes: Vec<usize> = four.iter().map(|z| z.size()).collect();
+        assert_eq!(vec![3, 3, 2, 1, 1, 1, 1], sizes);
+        assert_eq!(vec![p(7, 3), p(6, 4), p(7, 4)], four[0].cells);
+        assert_eq!(vec![p(0, 9), p(1, 9), p(2, 9)], four[1].cells);
+        assert_eq!(
+            (p(0, 9), p(2, 9), 2),
+            (four[1].min, four[1].max, four[1].peak)
+        );
+        assert_eq!((1.0, 9.0), four[1].centroid);
+
+        // diagonal neighbours join the middle of the map up
+        let eight = zones(&points, 2, Connectivity::Eight);
+        let sizes: Vec<usize> = eight.iter().map(|z| z.size()).collect();
+        assert_eq!(vec![7, 3, 1, 1], sizes);
+        let zone = &eight[0];
+        assert_eq!((p(3, 3), p(7, 5), 3), (zone.min, zone.max, zone.peak));
+        assert_eq!((37.0 / 7.0, 27.0 / 7.0), zone.centroid);
+
+        assert_eq!(2, zones(&points, 3, Connectivity::Four).len());
+        assert!(zones(&points, 4, Connectivity::Eight).is_empty());
+    }
+
+    #[test]
+    fn puzzle_input() {
+        let points = plot_points(load_input("input/input05.txt")).unwrap();
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let zones = zones(&points, 2, connectivity);
+            let total: usize = zones.iter().map(|z| z.size()).sum();
+            assert_eq!(20196, total);
+            assert!(zones.windows(2).all(|w| w[0].size() >= w[1].size()));
+        }
+    }
+}
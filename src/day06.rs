@@ -12,13 +12,13 @@ fn parse(input: &str) -> Vec<u64> {
 }
 
 fn load_input(filename: &str) -> Vec<u64> {
-    let input = read_to_string(&filename).unwrap();
+    let input = read_to_string(filename).unwrap();
     parse(&input)
 }
 
 // naive, literal simulation. Will not work for large populations.
-fn simulate_naive(start: &Vec<u64>, days: u64) -> Vec<u64> {
-    let mut population = start.clone();
+fn simulate_naive(start: &[u64], days: u64) -> Vec<u64> {
+    let mut population = start.to_vec();
 
     for _ in 0..days {
         // decrement each fish, or if zero reset (6) and spawn a new fish (8)
@@ -28,7 +28,7 @@ fn simulate_naive(start: &Vec<u64>, days: u64) -> Vec<u64> {
             .collect()
     }
 
-    return population;
+    population
 }
 
 // starting population --> histogram fish/day
@@ -46,7 +46,7 @@ fn simulate(start: &Vec<u64>, rounds: u64) -> [u64; 9] {
         days.rotate_left(1);
         days[6] += old_fish;
     }
-    return days;
+    days
 }
 
 #[cfg(test)]
@@ -13,20 +13,20 @@ fn parse(input: &str) -> Vec<i32> {
 }
 
 fn load_input(filename: &str) -> Vec<i32> {
-    let input = read_to_string(&filename).unwrap();
+    let input = read_to_string(filename).unwrap();
     parse(&input)
 }
 
-fn median(numbers: &mut Vec<i32>) -> i32 {
+fn median(numbers: &mut [i32]) -> i32 {
     numbers.sort();
     let mid = numbers.len() / 2;
     numbers[mid]
 }
 
-fn median_distance(positions: &mut Vec<i32>) -> i32 {
+fn median_distance(positions: &mut [i32]) -> i32 {
     let median = median(positions);
     let dists = positions.iter().map(|x| (x - median).abs());
-    return dists.sum();
+    dists.sum()
 }
 
 // sum of numbers up to n: n(n+1)/2
@@ -34,7 +34,7 @@ fn sum_up_to(n: i32) -> i32 {
     n * (n + 1) / 2
 }
 
-fn fuel_cost(positions: &Vec<i32>) -> i32 {
+fn fuel_cost(positions: &[i32]) -> i32 {
     let (&min, &max) = positions.iter().minmax().into_option().unwrap();
 
     // calc the total cost for all target positions, choose the cheapest
@@ -45,7 +45,7 @@ fn fuel_cost(positions: &Vec<i32>) -> i32 {
             .sum()
     });
 
-    return costs.min().unwrap();
+    costs.min().unwrap()
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
 mod day01;
 mod day02;
 mod day03;
-mod day04;
-mod day05;
+pub mod day04;
+pub mod day05;
 mod day06;
 mod day07;
@@ -0,0 +1,150 @@
+// Plays games against the bingo server over real sockets on localhost.
+
+use adventofcode_2021::day04::server::{serve, ServerConfig};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+const DRAWS: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1";
+
+const BOARDS: [&str; 3] = [
+    "22 13 17 11  0\n 8  2 23  4 24\n21  9 14 16  7\n 6 10  3 18  5\n 1 12 20 15 19\n",
+    " 3 15  0  2 22\n 9 18 13 17  5\n19  8  7 25 23\n20 11 10 24  4\n14 21 16 12  6\n",
+    "14 21 17 24  4\n10 16 15  9 19\n18  8 23 26 20\n22 11 13  6  5\n 2  0 12  3  7\n",
+];
+
+struct Client {
+    out: TcpStream,
+    lines: std::io::Lines<BufReader<TcpStream>>,
+}
+
+impl Client {
+    fn connect(port: u16) -> Self {
+        let out = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        out.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let lines = BufReader::new(out.try_clone().unwrap()).lines();
+        Client { out, lines }
+    }
+
+    fn send(&mut self, text: &str) {
+        self.out.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn recv(&mut self) -> String {
+        self.lines.next().unwrap().unwrap()
+    }
+
+    // Everything up to and including GAME OVER
+    fn rest_of_game(&mut self) -> Vec<String> {
+        let mut lines = vec![];
+        loop {
+            let line = self.recv();
+            let done = line == "GAME OVER";
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+}
+
+fn start_server(games: usize) -> (u16, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let config = ServerConfig {
+        draws: DRAWS.split(',').map(|x| x.parse().unwrap()).collect(),
+        delay: Duration::ZERO,
+        games: Some(games),
+        ..ServerConfig::default()
+    };
+    let handle = thread::spawn(move || serve(listener, config).unwrap());
+    (port, handle)
+}
+
+#[test]
+fn two_players() {
+    let (port, server) = start_server(1);
+
+    let mut alice = Client::connect(port);
+    assert_eq!("WELCOME 0", alice.recv());
+    alice.send("NAME alice\n");
+    assert_eq!("OK name alice", alice.recv());
+    let mut bob = Client::connect(port);
+    assert_eq!("WELCOME 1", bob.recv());
+    bob.send("name bob\n");
+    assert_eq!("OK name bob", bob.recv());
+
+    alice.send(&format!("BOARD\n{}\nBOARD\n{}\n", BOARDS[0], BOARDS[1]));
+    assert_eq!("OK board 0", alice.recv());
+    assert_eq!("OK board 1", alice.recv());
+    bob.send(&format!("BOARD\n{}\n", BOARDS[2]));
+    assert_eq!("OK board 2", bob.recv());
+
+    bob.send("START\n");
+    assert_eq!("OK start", bob.recv());
+    let bobs = bob.rest_of_game();
+    let alices = alice.rest_of_game();
+
+    assert_eq!("GAME 3 boards", bobs[0]);
+    assert_eq!("DRAW 1 7", bobs[1]);
+    // everyone hears every call and win, but only their own marks
+    assert_eq!("MARK 2 4 4", bobs[2]);
+    assert!(alices.contains(&"MARK 0 2 4".to_string()));
+    assert!(!alices.iter().any(|x| x.starts_with("MARK 2 ")));
+    assert!(!alices.iter().any(|x| x.starts_with("LINE 2 ")));
+    assert!(bobs.contains(&"LINE 2 row 0".to_string()));
+
+    // the example's first and last winners, with the puzzle's scores
+    let wins = |lines: &[String]| -> Vec<String> {
+        lines
+            .iter()
+            .filter(|x| x.starts_with("WIN"))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(wins(&alices), wins(&bobs));
+    let wins = wins(&bobs);
+    assert_eq!("WIN 2 4512 bob", wins[0]);
+    assert_eq!("WIN 1 1924 alice", wins[2]);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn bad_commands() {
+    let (port, server) = start_server(1);
+
+    let mut carol = Client::connect(port);
+    assert_eq!("WELCOME 0", carol.recv());
+    carol.send("START\n");
+    assert_eq!("ERROR no boards yet", carol.recv());
+    carol.send("SHOUT bingo\n");
+    assert_eq!("ERROR unknown command SHOUT", carol.recv());
+    carol.send("BOARD\n1 2\n3\n\n");
+    assert_eq!(
+        "ERROR RaggedBoard { board: 0, line: 2, expected: 2, found: 1 }",
+        carol.recv()
+    );
+    carol.send("BOARD\n1 x\n\n");
+    assert!(carol.recv().starts_with("ERROR BadNumber"));
+
+    // a 1x1 board wins on the first call
+    carol.send("BOARD\n7\n\nSTART\n");
+    assert_eq!("OK board 0", carol.recv());
+    assert_eq!("OK start", carol.recv());
+    let game = carol.rest_of_game();
+    assert_eq!(
+        vec![
+            "GAME 1 boards",
+            "DRAW 1 7",
+            "MARK 0 0 0",
+            "LINE 0 row 0",
+            "LINE 0 column 0"
+        ],
+        game[..5]
+    );
+    assert_eq!("WIN 0 0 player0", game[5]);
+
+    server.join().unwrap();
+}